@@ -0,0 +1,124 @@
+use flush::Engine;
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 5;
+const BASELINE: &str = include_str!("baseline.txt");
+
+const SOURCE: &str = "
+def fib(n) {
+    if (n < 2) {
+        return n
+    }
+
+    return fib(n - 1) + fib(n - 2)
+}
+
+def depth(n) {
+    if (n == 0) {
+        return 0
+    }
+
+    return depth(n - 1) + 1
+}
+
+def deepRecursion(times) {
+    def total = 0
+    def i = 0
+
+    while (i < times) {
+        total = total + depth(900)
+        i = i + 1
+    }
+
+    return total
+}
+
+def tightLoop(n) {
+    def sum = 0
+    def i = 0
+
+    while (i < n) {
+        sum = sum + i % 7
+        i = i + 1
+    }
+
+    return sum
+}
+
+def nestedScopes(n) {
+    def count = 0
+
+    for (i in range(0, n)) {
+        def j = 0
+
+        while (j < 10) {
+            count = count + 1
+            j = j + 1
+        }
+    }
+
+    return count
+}
+
+def first(list) {
+    return list[0]
+}
+
+def listArgument(times) {
+    def list = range(0, 100000)
+    def i = 0
+
+    while (i < times) {
+        first(list)
+        i = i + 1
+    }
+
+    return i
+}
+";
+
+fn bench(engine: &mut Engine, name: &str, arg: i64) {
+    let mut times: Vec<Duration> = vec![];
+
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        engine.call(name, vec![arg.into()]).unwrap();
+        times.push(start.elapsed());
+    }
+
+    times.sort();
+
+    let label = format!("{}({})", name, arg);
+    let median = times[RUNS / 2];
+    let baseline = BASELINE
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(' '))
+        .find(|(bench, _)| *bench == label)
+        .and_then(|(_, millis)| millis.parse::<f64>().ok());
+
+    print!(
+        "{:<24} min {:>9.2?}   median {:>9.2?}",
+        label, times[0], median
+    );
+
+    match baseline {
+        Some(millis) => println!(
+            "   baseline {:>9.2}ms   {:>6.1}x",
+            millis,
+            millis / (median.as_secs_f64() * 1000.0)
+        ),
+        None => println!(),
+    }
+}
+
+fn main() {
+    let mut engine = Engine::new();
+    engine.eval(SOURCE).unwrap();
+
+    bench(&mut engine, "fib", 22);
+    bench(&mut engine, "deepRecursion", 100);
+    bench(&mut engine, "tightLoop", 200000);
+    bench(&mut engine, "nestedScopes", 20000);
+    bench(&mut engine, "listArgument", 1000);
+}
@@ -0,0 +1,725 @@
+use crate::error::{similar_name, FlushError, Level, Result, Span};
+use crate::flush::{parse, process_file_path, read_file};
+use crate::interpreting::builtins;
+use crate::lexing::lexer::Lexer;
+use crate::lexing::typing::{Token, TokenKind};
+use crate::parsing::parser::Parser;
+use crate::parsing::typing::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Lint {
+    Undefined,   // undefined variables and functions
+    Duplicate,   // `def`s defined twice in the same scope
+    Unused,      // variables and parameters that are never read
+    Unreachable, // statements after `return`, `break` or `continue`
+    OutsideLoop, // `break` and `continue` outside a loop
+}
+
+impl Lint {
+    const ALL: [Lint; 5] = [
+        Lint::Undefined,
+        Lint::Duplicate,
+        Lint::Unused,
+        Lint::Unreachable,
+        Lint::OutsideLoop,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::Undefined => "undefined",
+            Lint::Duplicate => "duplicate",
+            Lint::Unused => "unused",
+            Lint::Unreachable => "unreachable",
+            Lint::OutsideLoop => "outside_loop",
+        }
+    }
+
+    fn default_level(&self) -> Option<Level> {
+        match self {
+            Lint::Undefined | Lint::Duplicate | Lint::OutsideLoop => Some(Level::Error),
+            Lint::Unused | Lint::Unreachable => Some(Level::Warning),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum BindingKind {
+    Variable,
+    Function,
+    Parameter,
+    Import,
+}
+
+#[derive(Clone, Debug)]
+pub struct Definition {
+    pub name: String,
+    pub span: Span,
+    pub params: Option<Vec<Param>>,
+}
+
+impl Definition {
+    fn new(name: &str, span: &Span, params: Option<&[Param]>) -> Self {
+        Self {
+            name: name.to_string(),
+            span: span.clone(),
+            params: params.map(|params| params.to_vec()),
+        }
+    }
+}
+
+pub struct Analysis {
+    pub diagnostics: Vec<FlushError>,
+    pub definitions: Vec<Definition>,
+    pub references: Vec<(Span, Definition)>,
+}
+
+struct Binding {
+    definition: Definition,
+    kind: BindingKind,
+    global: bool,
+    used: bool,
+}
+
+struct Scope {
+    names: HashMap<String, usize>,
+    transparent: bool,
+}
+
+type Function<'a> = (&'a [Param], &'a [Statement]);
+
+pub struct Checker<'a> {
+    file_path: PathBuf,
+    levels: HashMap<Lint, Option<Level>>,
+    builtins: Vec<&'static str>,
+    bindings: Vec<Binding>,
+    scopes: Vec<Scope>,
+    pending: Vec<Vec<Function<'a>>>,
+    loops: usize,
+    incomplete: bool,
+    diagnostics: Vec<(Option<Lint>, FlushError)>,
+    references: Vec<(Span, usize)>,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(file_path: PathBuf) -> Self {
+        Self {
+            file_path,
+            levels: Lint::ALL
+                .iter()
+                .map(|lint| (*lint, lint.default_level()))
+                .collect(),
+            builtins: builtins::names(),
+            bindings: vec![],
+            scopes: vec![],
+            pending: vec![],
+            loops: 0,
+            incomplete: false,
+            diagnostics: vec![],
+            references: vec![],
+        }
+    }
+
+    pub fn with_pragmas(mut self, tokens: &[Token]) -> Self {
+        for token in tokens {
+            if let TokenKind::Comment(text) = &token.kind {
+                if let Some(pragma) = text.trim().strip_prefix("flush:") {
+                    self.pragma(pragma, &token.span);
+                }
+            }
+        }
+
+        self
+    }
+
+    fn pragma(&mut self, mut pragma: &str, span: &Span) {
+        while !pragma.trim().is_empty() {
+            let (directive, lints) = match pragma.split_once('(') {
+                Some((directive, rest)) => match rest.split_once(')') {
+                    Some((lints, rest)) => {
+                        pragma = rest;
+                        (directive.trim(), lints)
+                    }
+                    None => return self.invalid_pragma(span),
+                },
+                None => return self.invalid_pragma(span),
+            };
+
+            let level = match directive {
+                "allow" => None,
+                "warn" => Some(Level::Warning),
+                "deny" => Some(Level::Error),
+                _ => return self.invalid_pragma(span),
+            };
+
+            for name in lints.split(',').map(str::trim) {
+                match Lint::ALL.iter().find(|lint| lint.name() == name) {
+                    Some(lint) => {
+                        self.levels.insert(*lint, level);
+                    }
+                    None => {
+                        let mut error =
+                            FlushError::new(span.clone(), format!("Unknown lint `{}`", name));
+
+                        if let Some(similar) =
+                            similar_name(name, Lint::ALL.iter().map(|lint| lint.name()))
+                        {
+                            error =
+                                error.with_help(format!("a similar lint exists: `{}`", similar));
+                        }
+
+                        self.diagnostics
+                            .push((None, error.with_level(Level::Warning)));
+                    }
+                }
+            }
+        }
+    }
+
+    fn invalid_pragma(&mut self, span: &Span) {
+        let error = FlushError::new(span.clone(), "Invalid lint pragma")
+            .with_level(Level::Warning)
+            .with_help("use `# flush: allow(lint, ...)`, `warn(...)` or `deny(...)`");
+
+        self.diagnostics.push((None, error));
+    }
+
+    fn report(&mut self, lint: Lint, error: FlushError) {
+        self.diagnostics.push((Some(lint), error));
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Scope {
+            names: HashMap::new(),
+            transparent: false,
+        });
+        self.pending.push(vec![]);
+    }
+
+    fn pop_scope(&mut self) {
+        while let Some(functions) = self.pending.last_mut().map(std::mem::take) {
+            if functions.is_empty() {
+                break;
+            }
+
+            for (params, statements) in functions {
+                self.function(params, statements);
+            }
+        }
+
+        let scope = self.scopes.pop().unwrap();
+        self.pending.pop();
+
+        let mut unused: Vec<&Binding> = scope
+            .names
+            .values()
+            .map(|index| &self.bindings[*index])
+            .filter(|binding| {
+                !binding.used && !binding.global && !binding.definition.name.starts_with('_')
+            })
+            .collect();
+
+        unused
+            .sort_by_key(|binding| (binding.definition.span.line, binding.definition.span.column));
+
+        let errors: Vec<FlushError> = unused
+            .into_iter()
+            .map(|binding| {
+                let Definition { name, span, .. } = &binding.definition;
+                let kind = match binding.kind {
+                    BindingKind::Function => "function",
+                    BindingKind::Parameter => "parameter",
+                    _ => "variable",
+                };
+
+                FlushError::new(
+                    span.clone(),
+                    format!("The {} `{}` is never used", kind, name),
+                )
+                .with_help(format!(
+                    "prefix it with an underscore to silence this warning: `_{}`",
+                    name
+                ))
+            })
+            .collect();
+
+        for error in errors {
+            self.report(Lint::Unused, error);
+        }
+    }
+
+    fn lookup(&self, id: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.names.get(id).copied())
+    }
+
+    fn lookup_current(&self, id: &str) -> Option<usize> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(index) = scope.names.get(id) {
+                return Some(*index);
+            }
+
+            if !scope.transparent {
+                break;
+            }
+        }
+
+        None
+    }
+
+    fn is_global(&self) -> bool {
+        self.scopes
+            .iter()
+            .rposition(|scope| !scope.transparent)
+            .is_some_and(|index| index == 0)
+    }
+
+    fn declare(&mut self, definition: Definition, kind: BindingKind) {
+        let Definition { name: id, span, .. } = &definition;
+        let existing = if kind == BindingKind::Parameter {
+            None
+        } else {
+            self.lookup_current(id)
+        };
+
+        if let Some(index) = existing {
+            let first = &self.bindings[index];
+
+            if kind == BindingKind::Import {
+                return;
+            }
+
+            let error = FlushError::new(
+                span.clone(),
+                format!("The `{}` variable already exists!", id),
+            )
+            .with_label(
+                first.definition.span.clone(),
+                format!("`{}` is first defined here", id),
+            );
+
+            self.report(Lint::Duplicate, error);
+        }
+
+        let index = self.bindings.len();
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .names
+            .insert(id.to_string(), index);
+
+        self.bindings.push(Binding {
+            global: self.is_global(),
+            used: kind == BindingKind::Import,
+            kind,
+            definition,
+        });
+    }
+
+    fn undefined(&mut self, message: String, id: &str, span: &Span) {
+        if self.incomplete {
+            return;
+        }
+
+        let mut error = FlushError::new(span.clone(), message);
+        let names = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.names.keys())
+            .map(|name| name.as_str())
+            .chain(self.builtins.iter().copied());
+
+        if let Some(name) = similar_name(id, names) {
+            error = error.with_help(format!("a similar name exists: `{}`", name));
+        }
+
+        self.report(Lint::Undefined, error);
+    }
+
+    fn use_binding(&mut self, index: usize, span: &Span) {
+        self.bindings[index].used = true;
+        self.references.push((span.clone(), index));
+    }
+
+    fn read(&mut self, id: &str, span: &Span) {
+        match self.lookup(id) {
+            Some(index) => self.use_binding(index, span),
+            None => self.undefined(format!("The variable `{}` cannot be found!", id), id, span),
+        }
+    }
+
+    fn write(&mut self, id: &str, span: &Span) {
+        let mut span = span.clone();
+        span.length = id.chars().count();
+        span.end_line = span.line;
+        span.end_column = span.column + span.length;
+
+        match self.lookup(id) {
+            Some(index) => self.references.push((span, index)),
+            None => self.undefined(format!("The variable `{}` cannot be found!", id), id, &span),
+        }
+    }
+
+    fn call(&mut self, id: &str, span: &Span) {
+        if self.builtins.contains(&id) {
+            return;
+        }
+
+        match self.lookup(id) {
+            Some(index) => self.use_binding(index, span),
+            None => self.undefined(format!("Undefined function `{}`!", id), id, span),
+        }
+    }
+
+    fn load(&mut self, raw_file_path: &str, span: &Span) {
+        if raw_file_path == self.file_path.to_string_lossy() {
+            return;
+        }
+
+        let mut visited = vec![process_file_path(&self.file_path.to_string_lossy())
+            .unwrap_or_else(|_| self.file_path.clone())];
+        let mut names = vec![];
+
+        match Self::imports(raw_file_path, &mut visited, &mut names) {
+            Ok(()) => {
+                for definition in names {
+                    self.declare(definition, BindingKind::Import);
+                }
+            }
+            Err(error) => {
+                self.incomplete = true;
+                self.diagnostics.push((None, error.or_span(span)));
+            }
+        }
+    }
+
+    fn imports(
+        raw_file_path: &str,
+        visited: &mut Vec<PathBuf>,
+        names: &mut Vec<Definition>,
+    ) -> Result<()> {
+        let file_path = process_file_path(raw_file_path)?;
+
+        if visited.contains(&file_path) {
+            return Ok(());
+        }
+
+        visited.push(file_path);
+
+        let (file_path, content) = read_file(raw_file_path)?;
+        let statements = parse(&content, file_path)?;
+
+        Self::exports(&statements, visited, names)
+    }
+
+    fn exports(
+        statements: &[Statement],
+        visited: &mut Vec<PathBuf>,
+        names: &mut Vec<Definition>,
+    ) -> Result<()> {
+        for statement in statements {
+            match &statement.kind {
+                StatementKind::VarDef(id, expr, _) => {
+                    names.push(Definition::new(id, &statement.span, Self::lambda(expr)))
+                }
+                StatementKind::FuncDef(id, params, _, _) => {
+                    names.push(Definition::new(id, &statement.span, Some(params)))
+                }
+                StatementKind::Load(raw_file_path) => Self::imports(raw_file_path, visited, names)
+                    .map_err(|error| error.or_span(&statement.span))?,
+                StatementKind::If(_, if_body, else_body) => {
+                    let body: Vec<Statement> = if_body
+                        .iter()
+                        .chain(else_body)
+                        .map(|statement| *statement.clone())
+                        .collect();
+
+                    Self::exports(&body, visited, names)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn function(&mut self, params: &'a [Param], statements: &'a [Statement]) {
+        let loops = std::mem::replace(&mut self.loops, 0);
+
+        self.push_scope();
+
+        for param in params {
+            if let ParamKind::Optional(_, default) = &param.kind {
+                self.expr(default);
+            }
+
+            self.declare(
+                Definition::new(param.name(), &param.span, None),
+                BindingKind::Parameter,
+            );
+        }
+
+        self.statements(statements.iter());
+        self.pop_scope();
+
+        self.loops = loops;
+    }
+
+    fn branch(&mut self, statements: &'a [Box<Statement>]) -> HashMap<String, usize> {
+        self.scopes.push(Scope {
+            names: HashMap::new(),
+            transparent: true,
+        });
+
+        self.statements(statements.iter().map(|statement| statement.as_ref()));
+
+        self.scopes.pop().unwrap().names
+    }
+
+    fn scoped_block(&mut self, statements: &'a [Box<Statement>]) {
+        self.push_scope();
+        self.statements(statements.iter().map(|statement| statement.as_ref()));
+        self.pop_scope();
+    }
+
+    fn statements(&mut self, statements: impl Iterator<Item = &'a Statement>) {
+        let mut terminated = false;
+        let mut unreachable: Option<(Span, Span)> = None;
+
+        for statement in statements {
+            if terminated {
+                unreachable = match unreachable {
+                    Some((first, _)) => Some((first, statement.span.clone())),
+                    None => Some((statement.span.clone(), statement.span.clone())),
+                };
+            }
+
+            terminated |= self.statement(statement);
+        }
+
+        if let Some((first, last)) = unreachable {
+            let error = FlushError::new(first.to(&last), "Unreachable code")
+                .with_help("remove it, or move it before the `return`, `break` or `continue`");
+
+            self.report(Lint::Unreachable, error);
+        }
+    }
+
+    fn statement(&mut self, statement: &'a Statement) -> bool {
+        let span = &statement.span;
+
+        match &statement.kind {
+            StatementKind::If(condition, if_body, else_body) => {
+                self.expr(condition);
+
+                let if_names = self.branch(if_body);
+                let else_names = self.branch(else_body);
+                let scope = &mut self.scopes.last_mut().unwrap().names;
+
+                for (id, index) in if_names.into_iter().chain(else_names) {
+                    scope.entry(id).or_insert(index);
+                }
+
+                return Self::terminates(if_body) && Self::terminates(else_body);
+            }
+            StatementKind::VarDef(id, expr, _) => {
+                self.expr(expr);
+                self.declare(
+                    Definition::new(id, span, Self::lambda(expr)),
+                    BindingKind::Variable,
+                );
+            }
+            StatementKind::FuncDef(id, params, statements, _) => {
+                self.declare(
+                    Definition::new(id, span, Some(params)),
+                    BindingKind::Function,
+                );
+                self.pending.last_mut().unwrap().push((params, statements));
+            }
+            StatementKind::VarSet(id, expr, _) => {
+                self.expr(expr);
+                self.write(id, span);
+            }
+            StatementKind::IndexSet(expr, index, value) => {
+                self.expr(value);
+                self.expr(expr);
+                self.expr(index);
+            }
+            StatementKind::Return(expr) => {
+                self.expr(expr);
+                return true;
+            }
+            StatementKind::While(condition, statements) => {
+                self.expr(condition);
+                self.loops += 1;
+                self.scoped_block(statements);
+                self.loops -= 1;
+            }
+            StatementKind::For(id, id_span, expr, statements, _) => {
+                self.expr(expr);
+
+                if let Some(index) = self.lookup(id) {
+                    let error = FlushError::new(
+                        id_span.clone(),
+                        format!("The `{}` variable already exists!", id),
+                    )
+                    .with_label(
+                        self.bindings[index].definition.span.clone(),
+                        format!("`{}` is first defined here", id),
+                    );
+
+                    self.report(Lint::Duplicate, error);
+                }
+
+                self.loops += 1;
+                self.push_scope();
+                self.declare(Definition::new(id, id_span, None), BindingKind::Variable);
+                self.statements(statements.iter().map(|statement| statement.as_ref()));
+                self.pop_scope();
+                self.loops -= 1;
+            }
+            StatementKind::Break | StatementKind::Continue => {
+                if self.loops == 0 {
+                    let keyword = match statement.kind {
+                        StatementKind::Break => "break",
+                        _ => "continue",
+                    };
+                    let error = FlushError::new(
+                        span.clone(),
+                        format!("The {} keyword cannot be used outside a loop!", keyword),
+                    );
+
+                    self.report(Lint::OutsideLoop, error);
+                }
+
+                return true;
+            }
+            StatementKind::Load(raw_file_path) => self.load(raw_file_path, span),
+            StatementKind::Expr(expr) => self.expr(expr),
+        }
+
+        false
+    }
+
+    fn lambda(expr: &Expr) -> Option<&[Param]> {
+        match &expr.kind {
+            ExprKind::Lambda(params, _) => Some(params),
+            _ => None,
+        }
+    }
+
+    fn terminates(statements: &[Box<Statement>]) -> bool {
+        statements.iter().any(|statement| match &statement.kind {
+            StatementKind::Return(_) | StatementKind::Break | StatementKind::Continue => true,
+            StatementKind::If(_, if_body, else_body) => {
+                Self::terminates(if_body) && Self::terminates(else_body)
+            }
+            _ => false,
+        })
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        match &expr.kind {
+            ExprKind::String(_)
+            | ExprKind::Int(_)
+            | ExprKind::BigInt(_)
+            | ExprKind::Float(_)
+            | ExprKind::Boolean(_) => {}
+            ExprKind::Var(id, _) => self.read(id, &expr.span),
+            ExprKind::Call(function, args, named_args) => {
+                match &function.kind {
+                    ExprKind::Var(id, _) => self.call(id, &function.span),
+                    _ => self.expr(function),
+                }
+
+                for arg in args.iter().chain(named_args.iter().map(|(_, arg)| arg)) {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::List(list) => {
+                for element in list {
+                    self.expr(element);
+                }
+            }
+            ExprKind::Dictionnary(dict) => {
+                let mut values: Vec<&'a Box<Expr>> = dict.values().collect();
+                values.sort_by_key(|value| (value.span.line, value.span.column));
+
+                for value in values {
+                    self.expr(value);
+                }
+            }
+            ExprKind::Index(expr, index) => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::Lambda(params, statements) => {
+                self.pending.last_mut().unwrap().push((params, statements));
+            }
+            ExprKind::BinOp(_, left, right) => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary(_, expr) => self.expr(expr),
+        }
+    }
+
+    pub fn analyze(mut self, statements: &'a [Statement]) -> Analysis {
+        self.push_scope();
+        self.statements(statements.iter());
+        self.pop_scope();
+
+        let levels = self.levels;
+        let mut diagnostics: Vec<FlushError> = self
+            .diagnostics
+            .into_iter()
+            .filter_map(|(lint, error)| match lint {
+                Some(lint) => {
+                    levels[&lint].map(|level| error.with_level(level).with_code(lint.name()))
+                }
+                None => Some(error),
+            })
+            .collect();
+
+        diagnostics.sort_by_key(|error| (error.span.line, error.span.column));
+
+        let bindings = self.bindings;
+        let references = self
+            .references
+            .into_iter()
+            .map(|(span, index)| (span, bindings[index].definition.clone()))
+            .collect();
+
+        Analysis {
+            diagnostics,
+            definitions: bindings
+                .into_iter()
+                .map(|binding| binding.definition)
+                .collect(),
+            references,
+        }
+    }
+}
+
+pub fn analyze(content: &str, file_path: PathBuf) -> Result<Analysis> {
+    let mut lexer = Lexer::new(content, file_path.clone()).with_comments();
+    let tokens = lexer.tokenize()?.clone();
+
+    let code: Vec<Token> = tokens
+        .iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Comment(_)))
+        .cloned()
+        .collect();
+    let statements = Parser::new(&code).parse()?;
+
+    Ok(Checker::new(file_path)
+        .with_pragmas(&tokens)
+        .analyze(&statements))
+}
+
+pub fn check(content: &str, file_path: PathBuf) -> Result<Vec<FlushError>> {
+    Ok(analyze(content, file_path)?.diagnostics)
+}
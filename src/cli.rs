@@ -0,0 +1,256 @@
+use crate::checking::checker;
+use crate::error::{style, FlushError, Level, Result};
+use crate::flush::Backend;
+use crate::formatting::formatter;
+use crate::interpreting::modules::ModuleRegistry;
+use crate::{flush, lsp, repl, test_runner};
+use ansi_term::Colour::Red;
+use std::fs::write;
+use std::io::{stdin, Read};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: flush [options] [file] [args...]
+       flush <command> [arguments]
+
+Commands:
+    run <file> [-- args]    Run a script, `-` reads it from the standard input
+    check <files>           Report errors and warnings without running files
+    fmt [--check] <files>   Format files in place, `--check` only reports them
+    repl                    Start the interactive REPL, the default without arguments
+    test [paths]            Run the `test` functions of files and directories
+    lsp                     Start a language server speaking LSP over stdio
+
+Options:
+    -e <code>               Run inline code
+    --vm                    Run on the bytecode virtual machine
+    -h, --help              Print this help
+    -V, --version           Print the version";
+
+pub enum Source {
+    File(String),
+    Stdin,
+    Inline(String),
+}
+
+pub enum Command {
+    Run(Source, Vec<String>, Backend),
+    Check(Vec<Source>),
+    Fmt(bool, Vec<Source>),
+    Repl,
+    Test(Vec<String>),
+    Lsp,
+    Version,
+    Help,
+}
+
+pub fn parse(args: &[String]) -> std::result::Result<Command, String> {
+    let (first, rest) = match args.split_first() {
+        Some((first, rest)) => (first.as_str(), rest),
+        None => return Ok(Command::Repl),
+    };
+
+    Ok(match first {
+        "-h" | "--help" => Command::Help,
+        "-V" | "--version" => Command::Version,
+        "run" => parse_run(rest)?,
+        "check" if rest.is_empty() => return Err("`check` expects at least one file".to_string()),
+        "check" => Command::Check(rest.iter().map(|arg| source(arg)).collect()),
+        "fmt" => parse_fmt(rest)?,
+        "repl" if rest.is_empty() => Command::Repl,
+        "repl" => return Err("`repl` does not take arguments".to_string()),
+        "test" => Command::Test(rest.to_vec()),
+        "lsp" if rest.is_empty() => Command::Lsp,
+        "lsp" => return Err("`lsp` does not take arguments".to_string()),
+        _ => parse_run(args)?,
+    })
+}
+
+fn parse_run(args: &[String]) -> std::result::Result<Command, String> {
+    let (backend, args) = match args.split_first() {
+        Some((first, rest)) if first == "--vm" => (Backend::Vm, rest),
+        _ => (Backend::Interpreter, args),
+    };
+
+    let (first, rest) = match args.split_first() {
+        Some((first, rest)) => (first.as_str(), rest),
+        None => return Err("`run` expects a file".to_string()),
+    };
+
+    let (source, script_args) = match first {
+        "-e" => match rest.split_first() {
+            Some((code, rest)) => (Source::Inline(code.clone()), rest),
+            None => return Err("`-e` expects some code".to_string()),
+        },
+        "-" => (Source::Stdin, rest),
+        option if option.starts_with('-') && option != "--" => {
+            return Err(format!("Unknown option `{}`", option))
+        }
+        file => (Source::File(file.to_string()), rest),
+    };
+
+    let script_args = match script_args.split_first() {
+        Some((separator, script_args)) if separator == "--" => script_args,
+        _ => script_args,
+    };
+
+    Ok(Command::Run(source, script_args.to_vec(), backend))
+}
+
+fn parse_fmt(args: &[String]) -> std::result::Result<Command, String> {
+    let check = args.iter().any(|arg| arg == "--check");
+    let files: Vec<Source> = args
+        .iter()
+        .filter(|arg| *arg != "--check")
+        .map(|arg| source(arg))
+        .collect();
+
+    if files.is_empty() {
+        return Err("`fmt` expects at least one file".to_string());
+    }
+
+    Ok(Command::Fmt(check, files))
+}
+
+fn source(arg: &str) -> Source {
+    match arg {
+        "-" => Source::Stdin,
+        file => Source::File(file.to_string()),
+    }
+}
+
+fn read_source(source: &Source) -> Result<(PathBuf, String)> {
+    match source {
+        Source::File(raw_file_path) => flush::read_file(raw_file_path),
+        Source::Inline(code) => Ok((PathBuf::from("<inline>"), code.clone())),
+        Source::Stdin => {
+            let mut content = String::new();
+
+            match stdin().read_to_string(&mut content) {
+                Ok(_) => Ok((PathBuf::from("<stdin>"), content)),
+                Err(e) => Err(format!("Could not read the standard input: {}", e).into()),
+            }
+        }
+    }
+}
+
+fn run(source: &Source, args: Vec<String>, backend: Backend) -> Result<()> {
+    let (file_path, content) = read_source(source)?;
+
+    if let Ok(diagnostics) = checker::check(&content, file_path.clone()) {
+        for diagnostic in diagnostics {
+            eprintln!("{}\n", diagnostic.with_level(Level::Warning));
+        }
+    }
+
+    flush::run_source(
+        &content,
+        file_path,
+        &ModuleRegistry::shared(),
+        true,
+        args,
+        backend,
+    )?;
+
+    Ok(())
+}
+
+fn check(source: &Source) -> Result<()> {
+    let (file_path, content) = read_source(source)?;
+    let diagnostics = checker::check(&content, file_path)?;
+    let failed = diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.level == Level::Error);
+
+    for diagnostic in diagnostics {
+        eprintln!("{}\n", diagnostic);
+    }
+
+    if failed {
+        return Err(FlushError::exit(1));
+    }
+
+    Ok(())
+}
+
+fn fmt(source: &Source, check: bool) -> Result<bool> {
+    let (file_path, content) = read_source(source)?;
+    let formatted = formatter::format(&content, file_path.clone())?;
+    let changed = formatted != content;
+
+    match source {
+        Source::File(_) if check => {
+            if changed {
+                println!("{} is not formatted", file_path.display());
+            }
+        }
+        Source::File(_) => {
+            if changed {
+                if let Err(e) = write(&file_path, formatted) {
+                    return Err(format!("Could not write `{}`: {}", file_path.display(), e).into());
+                }
+            }
+        }
+        _ if check => {}
+        _ => print!("{}", formatted),
+    }
+
+    Ok(changed)
+}
+
+fn report(result: Result<()>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) => match e.exit_code {
+            Some(code) => code,
+            None => {
+                eprintln!("{}", e);
+                1
+            }
+        },
+    }
+}
+
+pub fn execute(command: Command) -> i32 {
+    match command {
+        Command::Run(source, args, backend) => report(run(&source, args, backend)),
+        Command::Check(sources) => sources
+            .iter()
+            .map(|source| report(check(source)))
+            .max()
+            .unwrap_or(0),
+        Command::Fmt(check, sources) => sources
+            .iter()
+            .map(|source| match fmt(source, check) {
+                Ok(changed) => (check && changed) as i32,
+                Err(e) => report(Err(e)),
+            })
+            .max()
+            .unwrap_or(0),
+        Command::Repl => repl::start(),
+        Command::Test(paths) => test_runner::run(&paths),
+        Command::Lsp => lsp::start(),
+        Command::Version => {
+            println!("flush {}", env!("CARGO_PKG_VERSION"));
+            0
+        }
+        Command::Help => {
+            println!("{}", USAGE);
+            0
+        }
+    }
+}
+
+pub fn start(args: &[String]) -> i32 {
+    match parse(args) {
+        Ok(command) => execute(command),
+        Err(message) => {
+            eprintln!(
+                "{}: {}\n\n{}",
+                style(Red.bold()).paint("error"),
+                message,
+                USAGE
+            );
+            2
+        }
+    }
+}
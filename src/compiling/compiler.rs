@@ -0,0 +1,586 @@
+use crate::compiling::typing::{Capture, Function, Instruction, Location};
+use crate::error::Span;
+use crate::interpreting::builtins;
+use crate::interpreting::resolver::layout;
+use crate::interpreting::typing::Literal;
+use crate::parsing::typing::*;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+struct Loop {
+    start: usize,
+    breaks: Vec<usize>,
+}
+
+struct Unit {
+    function: Function,
+    loops: Vec<Loop>,
+    names: HashMap<String, u32>,
+}
+
+// A block of the resolver, laid out from `base` in the locals of one unit
+struct Block {
+    unit: usize,
+    base: usize,
+}
+
+pub struct Compiler {
+    units: Vec<Unit>,
+    blocks: Vec<Block>,
+    repl: bool,
+}
+
+impl Compiler {
+    // With `repl`, top-level expressions are evaluated and the last statement,
+    // if it is an expression, gives the value the script returns
+    pub fn compile(statements: &[Statement], repl: bool) -> Function {
+        let mut compiler = Self {
+            units: vec![Unit {
+                function: Function {
+                    name: "<script>".to_string(),
+                    ..Function::default()
+                },
+                loops: vec![],
+                names: HashMap::new(),
+            }],
+            blocks: vec![],
+            repl,
+        };
+
+        match statements.split_last() {
+            Some((last, statements)) if repl => {
+                for statement in statements {
+                    compiler.statement(statement);
+                }
+
+                match &last.kind {
+                    StatementKind::Expr(expr) => compiler.expr(expr),
+                    _ => {
+                        compiler.statement(last);
+                        compiler.constant(Literal::None, &last.span);
+                    }
+                }
+
+                compiler.emit(Instruction::Return, &last.span);
+            }
+            _ => {
+                for statement in statements {
+                    compiler.statement(statement);
+                }
+
+                compiler.constant(Literal::None, &Span::default());
+                compiler.emit(Instruction::Return, &Span::default());
+            }
+        }
+
+        compiler.units.pop().unwrap().function
+    }
+
+    fn unit(&mut self) -> &mut Unit {
+        self.units.last_mut().unwrap()
+    }
+
+    fn is_script(&self) -> bool {
+        self.units.len() == 1
+    }
+
+    fn name(&mut self, id: &str) -> u32 {
+        let unit = self.unit();
+
+        if let Some(index) = unit.names.get(id) {
+            return *index;
+        }
+
+        let index = unit.function.names.len() as u32;
+        unit.function.names.push(id.to_string());
+        unit.names.insert(id.to_string(), index);
+
+        index
+    }
+
+    fn position(&self) -> usize {
+        self.units.last().unwrap().function.code.len()
+    }
+
+    fn emit(&mut self, instruction: Instruction, span: &Span) -> usize {
+        let function = &mut self.unit().function;
+
+        function.code.push(instruction);
+        function.spans.push(span.clone());
+
+        function.code.len() - 1
+    }
+
+    fn patch(&mut self, index: usize) {
+        let position = self.position() as u32;
+
+        match &mut self.unit().function.code[index] {
+            Instruction::Jump(target)
+            | Instruction::JumpUnless(target)
+            | Instruction::JumpIfDefined(_, target)
+            | Instruction::Next(target) => *target = position,
+            _ => unreachable!(),
+        }
+    }
+
+    fn constant(&mut self, literal: Literal, span: &Span) {
+        let constants = &mut self.unit().function.constants;
+        constants.push(literal);
+
+        let index = constants.len() - 1;
+        self.emit(Instruction::Constant(index as u32), span);
+    }
+
+    fn fail(&mut self, message: impl ToString, help: Option<&str>, span: &Span) {
+        let failures = &mut self.unit().function.failures;
+        failures.push((message.to_string(), help.map(|help| help.to_string())));
+
+        let index = failures.len() - 1;
+        self.emit(Instruction::Fail(index as u32), span);
+    }
+
+    fn keys(&mut self, keys: Vec<String>) -> u32 {
+        let table = &mut self.unit().function.keys;
+        table.push(keys);
+
+        (table.len() - 1) as u32
+    }
+
+    fn allocate(&mut self, layout: Vec<(String, Span)>) -> (usize, usize) {
+        let unit = self.units.len() - 1;
+        let function = &mut self.unit().function;
+        let start = function.locals.len();
+
+        function.captured.resize(start + layout.len(), false);
+        function.locals.extend(layout);
+
+        let end = function.locals.len();
+        self.blocks.push(Block { unit, base: start });
+
+        (start, end)
+    }
+
+    fn enter(&mut self, layout: Vec<(String, Span)>, span: &Span) {
+        let (start, end) = self.allocate(layout);
+
+        self.emit(Instruction::Enter(start as u32, end as u32), span);
+    }
+
+    fn leave(&mut self) {
+        self.blocks.pop();
+    }
+
+    fn resolve(&mut self, id: &str, resolution: &Resolution) -> Location {
+        match resolution {
+            Resolution::Global => Location::Global(self.name(id)),
+            Resolution::Local(depth, slot) => self.local(*depth, *slot),
+        }
+    }
+
+    fn local(&mut self, depth: usize, slot: usize) -> Location {
+        let block = &self.blocks[self.blocks.len() - 1 - depth];
+        let (unit, slot) = (block.unit, block.base + slot);
+
+        self.capture(self.units.len() - 1, unit, slot)
+    }
+
+    fn capture(&mut self, depth: usize, unit: usize, slot: usize) -> Location {
+        if depth == unit {
+            return Location::Local(slot as u32);
+        }
+
+        let (capture, name, span) = match self.capture(depth - 1, unit, slot) {
+            Location::Local(slot) => {
+                let enclosing = &mut self.units[depth - 1].function;
+                enclosing.captured[slot as usize] = true;
+
+                let (name, span) = enclosing.locals[slot as usize].clone();
+                (Capture::Local(slot), name, span)
+            }
+            Location::Upvalue(index) => {
+                let (_, name, span) = &self.units[depth - 1].function.captures[index as usize];
+                (Capture::Upvalue(index), name.clone(), span.clone())
+            }
+            Location::Global(_) => unreachable!(),
+        };
+
+        let captures = &mut self.units[depth].function.captures;
+
+        match captures.iter().position(|(other, _, _)| *other == capture) {
+            Some(index) => Location::Upvalue(index as u32),
+            None => {
+                captures.push((capture, name, span));
+                Location::Upvalue((captures.len() - 1) as u32)
+            }
+        }
+    }
+
+    fn block(&mut self, statements: &[Box<Statement>]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        let span = &statement.span;
+
+        match &statement.kind {
+            StatementKind::VarDef(id, expr, resolution) => {
+                self.expr(expr);
+
+                let location = self.resolve(id, resolution);
+                self.emit(Instruction::Define(location), span);
+            }
+            StatementKind::FuncDef(id, params, statements, resolution) => {
+                let index = self.function(id, params, statements, span);
+                self.emit(Instruction::Closure(index), span);
+
+                let location = self.resolve(id, resolution);
+                self.emit(Instruction::Bind(location), span);
+
+                if id == "main" {
+                    self.emit(Instruction::Get(location), span);
+                    self.emit(Instruction::CallMain, span);
+                    self.emit(Instruction::Pop, span);
+                }
+            }
+            StatementKind::VarSet(id, expr, resolution) => {
+                self.expr(expr);
+
+                let location = self.resolve(id, resolution);
+                self.emit(Instruction::Set(location), span);
+            }
+            StatementKind::IndexSet(expr, index, value) => {
+                self.expr(value);
+                self.expr(expr);
+                self.expr(index);
+                self.emit(Instruction::IndexSet, span);
+            }
+            StatementKind::Return(expr) => {
+                self.expr(expr);
+                self.emit(Instruction::Return, span);
+            }
+            StatementKind::While(condition, statements) => {
+                self.while_loop(condition, statements, span)
+            }
+            StatementKind::For(id, id_span, list, statements, resolution) => {
+                self.for_loop(id, id_span, resolution, list, statements, span)
+            }
+            StatementKind::Break => match self.unit().loops.is_empty() {
+                true => self.fail(
+                    "The break keyword cannot be used outside a loop!",
+                    None,
+                    span,
+                ),
+                false => {
+                    let jump = self.emit(Instruction::Jump(0), span);
+                    self.unit().loops.last_mut().unwrap().breaks.push(jump);
+                }
+            },
+            StatementKind::Continue => match self.unit().loops.last() {
+                Some(Loop { start, .. }) => {
+                    let start = *start as u32;
+                    self.emit(Instruction::Jump(start), span);
+                }
+                None => self.fail(
+                    "The continue keyword cannot be used outside a loop!",
+                    None,
+                    span,
+                ),
+            },
+            StatementKind::Load(file_path) => {
+                let index = self.name(file_path);
+                self.emit(Instruction::Load(index), span);
+            }
+            StatementKind::If(condition, if_body, else_body) => {
+                self.expr(condition);
+                let else_jump = self.emit(Instruction::JumpUnless(0), span);
+
+                self.block(if_body);
+                let end_jump = self.emit(Instruction::Jump(0), span);
+
+                self.patch(else_jump);
+                self.block(else_body);
+                self.patch(end_jump);
+            }
+            StatementKind::Expr(expr) => {
+                if self.is_script() && !self.repl {
+                    self.fail(
+                        "Cannot evaluate expression outside a function!",
+                        Some("move it into the body of `def main() { ... }`"),
+                        span,
+                    );
+                } else {
+                    self.expr(expr);
+                    self.emit(Instruction::Pop, span);
+                }
+            }
+        }
+    }
+
+    fn start_loop(&mut self, start: usize) {
+        self.unit().loops.push(Loop {
+            start,
+            breaks: vec![],
+        });
+    }
+
+    fn while_loop(&mut self, condition: &Expr, statements: &[Box<Statement>], span: &Span) {
+        let start = self.position();
+
+        self.expr(condition);
+        let end_jump = self.emit(Instruction::JumpUnless(0), span);
+
+        self.start_loop(start);
+
+        self.enter(layout(vec![], statements.iter().map(Box::as_ref)), span);
+        self.block(statements);
+        self.leave();
+
+        self.emit(Instruction::Jump(start as u32), span);
+        self.patch(end_jump);
+
+        let current = self.unit().loops.pop().unwrap();
+
+        for jump in current.breaks {
+            self.patch(jump);
+        }
+    }
+
+    fn for_loop(
+        &mut self,
+        id: &str,
+        id_span: &Span,
+        resolution: &Resolution,
+        list: &Expr,
+        statements: &[Box<Statement>],
+        span: &Span,
+    ) {
+        let location = self.resolve(id, resolution);
+        self.emit(Instruction::Unique(location), span);
+
+        self.expr(list);
+        self.emit(Instruction::Iterate, span);
+
+        let start = self.position();
+        let end_jump = self.emit(Instruction::Next(0), span);
+
+        self.start_loop(start);
+
+        let slots = vec![(id.to_string(), id_span.clone())];
+        self.enter(layout(slots, statements.iter().map(Box::as_ref)), span);
+
+        let location = self.resolve(id, &Resolution::Local(0, 0));
+        self.emit(Instruction::Bind(location), span);
+        self.block(statements);
+        self.leave();
+
+        self.emit(Instruction::Jump(start as u32), span);
+        self.patch(end_jump);
+
+        let current = self.unit().loops.pop().unwrap();
+
+        for jump in current.breaks {
+            self.patch(jump);
+        }
+
+        self.emit(Instruction::Pop, span);
+        self.emit(Instruction::Pop, span);
+    }
+
+    fn function(
+        &mut self,
+        name: &str,
+        params: &[Param],
+        statements: &[Statement],
+        span: &Span,
+    ) -> u32 {
+        self.units.push(Unit {
+            function: Function {
+                name: name.to_string(),
+                params: params.to_vec(),
+                ..Function::default()
+            },
+            loops: vec![],
+            names: HashMap::new(),
+        });
+
+        let slots = params
+            .iter()
+            .map(|param| (param.name().to_string(), param.span.clone()))
+            .collect();
+
+        self.allocate(layout(slots, statements));
+
+        for (slot, param) in params.iter().enumerate() {
+            if let ParamKind::Optional(_, default) = &param.kind {
+                let slot = slot as u32;
+                let skip_jump = self.emit(Instruction::JumpIfDefined(slot, 0), &param.span);
+
+                self.expr(default);
+                self.emit(Instruction::Define(Location::Local(slot)), &param.span);
+                self.patch(skip_jump);
+            }
+        }
+
+        for statement in statements {
+            self.statement(statement);
+        }
+
+        self.constant(Literal::None, span);
+        self.emit(Instruction::Return, span);
+        self.leave();
+
+        let unit = self.units.pop().unwrap();
+        let functions = &mut self.unit().function.functions;
+        functions.push(Rc::new(unit.function));
+
+        (functions.len() - 1) as u32
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        let span = &expr.span;
+
+        match &expr.kind {
+            ExprKind::String(string) => {
+                self.constant(Literal::String(string.as_str().into()), span)
+            }
+            ExprKind::Int(int) => self.constant(Literal::Int(*int), span),
+            ExprKind::BigInt(int) => self.constant(Literal::BigInt(int.clone()), span),
+            ExprKind::Float(float) => self.constant(Literal::Float(*float), span),
+            ExprKind::Boolean(boolean) => self.constant(Literal::Boolean(*boolean), span),
+            ExprKind::Var(id, resolution) => {
+                let location = self.resolve(id, resolution);
+                self.emit(Instruction::Get(location), span);
+            }
+            ExprKind::Call(function, args, named_args) => {
+                self.call(function, args, named_args, span)
+            }
+            ExprKind::List(list) => {
+                for element in list {
+                    self.expr(element);
+                }
+
+                self.emit(Instruction::List(list.len() as u32), span);
+            }
+            ExprKind::Dictionnary(dict) => {
+                let mut keys = vec![];
+
+                for (key, value) in dict {
+                    self.expr(value);
+                    keys.push(key.clone());
+                }
+
+                let index = self.keys(keys);
+                self.emit(Instruction::Dictionnary(index), span);
+            }
+            ExprKind::Index(list, index) => {
+                self.expr(list);
+                self.expr(index);
+                self.emit(Instruction::Index, span);
+            }
+            ExprKind::Lambda(params, statements) => {
+                let index = self.function("lambda", params, statements, span);
+                self.emit(Instruction::Closure(index), span);
+            }
+            ExprKind::BinOp(BinOp::And, left, right) => {
+                self.expr(left);
+                let false_jump = self.emit(Instruction::JumpUnless(0), &left.span);
+
+                self.expr(right);
+                self.emit(Instruction::Condition, &right.span);
+                let end_jump = self.emit(Instruction::Jump(0), span);
+
+                self.patch(false_jump);
+                self.constant(Literal::Boolean(false), span);
+                self.patch(end_jump);
+            }
+            ExprKind::BinOp(BinOp::Or, left, right) => {
+                self.expr(left);
+                let right_jump = self.emit(Instruction::JumpUnless(0), &left.span);
+
+                self.constant(Literal::Boolean(true), span);
+                let end_jump = self.emit(Instruction::Jump(0), span);
+
+                self.patch(right_jump);
+                self.expr(right);
+                self.emit(Instruction::Condition, &right.span);
+                self.patch(end_jump);
+            }
+            ExprKind::BinOp(op, left, right) => {
+                self.expr(left);
+                self.expr(right);
+                self.emit(Instruction::Binary(*op), span);
+            }
+            ExprKind::Unary(UnaryOp::Neg, expr) => {
+                self.expr(expr);
+                self.emit(Instruction::Negate, span);
+            }
+            ExprKind::Unary(UnaryOp::Not, expr) => {
+                self.expr(expr);
+                self.emit(Instruction::Not, span);
+            }
+        }
+    }
+
+    fn call(
+        &mut self,
+        function: &Expr,
+        args: &[Box<Expr>],
+        named_args: &[(String, Box<Expr>)],
+        span: &Span,
+    ) {
+        if let ExprKind::Var(id, _) = &function.kind {
+            if let Some(index) = builtins::index(id) {
+                return self.call_builtin(id, index, args, named_args, span);
+            }
+        }
+
+        match &function.kind {
+            ExprKind::Var(id, resolution) => {
+                let location = self.resolve(id, resolution);
+                self.emit(Instruction::GetFunction(location), span);
+            }
+            _ => self.expr(function),
+        }
+
+        for arg in args {
+            self.expr(arg);
+        }
+
+        if named_args.is_empty() {
+            self.emit(Instruction::Call(args.len() as u32), span);
+            return;
+        }
+
+        let mut names = vec![];
+
+        for (name, expr) in named_args {
+            self.expr(expr);
+            names.push(name.clone());
+        }
+
+        let index = self.keys(names);
+        self.emit(Instruction::CallNamed(args.len() as u32, index), span);
+    }
+    fn call_builtin(
+        &mut self,
+        id: &str,
+        index: usize,
+        args: &[Box<Expr>],
+        named_args: &[(String, Box<Expr>)],
+        span: &Span,
+    ) {
+        if !named_args.is_empty() {
+            let message = format!("`{}` does not accept named arguments!", id);
+            return self.fail(message, None, span);
+        }
+
+        for arg in args {
+            self.expr(arg);
+        }
+
+        let instruction = Instruction::CallBuiltin(index as u32, args.len() as u32);
+        self.emit(instruction, span);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod compiler;
+pub mod typing;
+pub mod vm;
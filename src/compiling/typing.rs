@@ -0,0 +1,84 @@
+use crate::error::Span;
+use crate::interpreting::typing::{Literal, Scope};
+use crate::parsing::typing::{BinOp, Param};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+pub type Cell = Rc<RefCell<Option<Literal>>>;
+
+// Operands index into the tables of the function the instruction belongs to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Location {
+    Local(u32),
+    Upvalue(u32),
+    Global(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Capture {
+    Local(u32),
+    Upvalue(u32),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Instruction {
+    Constant(u32),
+    Pop,
+    Get(Location),
+    GetFunction(Location),
+    Set(Location),
+    Define(Location),
+    Bind(Location),
+    Unique(Location),
+    Enter(u32, u32),
+    List(u32),
+    Dictionnary(u32),
+    Index,
+    IndexSet,
+    Binary(BinOp),
+    Negate,
+    Not,
+    Condition,
+    Jump(u32),
+    JumpUnless(u32),
+    JumpIfDefined(u32, u32),
+    Iterate,
+    Next(u32),
+    Closure(u32),
+    Call(u32),
+    CallNamed(u32, u32),
+    CallBuiltin(u32, u32),
+    CallMain,
+    Return,
+    Load(u32),
+    Fail(u32),
+}
+
+#[derive(Debug, Default)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub code: Vec<Instruction>,
+    pub spans: Vec<Span>,
+    pub constants: Vec<Literal>,
+    pub names: Vec<String>,
+    pub keys: Vec<Vec<String>>,
+    pub failures: Vec<(String, Option<String>)>,
+    pub functions: Vec<Rc<Function>>,
+    pub locals: Vec<(String, Span)>,
+    pub captured: Vec<bool>,
+    pub captures: Vec<(Capture, String, Span)>,
+}
+
+pub struct Closure {
+    pub function: Rc<Function>,
+    pub upvalues: Vec<Cell>,
+    pub globals: Scope,
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Closure({})", self.function.name)
+    }
+}
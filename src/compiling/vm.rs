@@ -0,0 +1,787 @@
+use crate::compiling::compiler::Compiler;
+use crate::compiling::typing::{Capture, Cell, Closure, Function, Instruction, Location};
+use crate::error::{similar_name, FlushError, Frame, Result, Span};
+use crate::flush::{run, Backend};
+use crate::interpreting::builtins::{self, Runtime, BUILTINS};
+use crate::interpreting::gc::{Heap, Object};
+use crate::interpreting::interpreter::{Interpreter, MAX_CALL_DEPTH};
+use crate::interpreting::modules::Modules;
+use crate::interpreting::resolver::Resolver;
+use crate::interpreting::typing::{Literal, Scope, Variable};
+use crate::parsing::typing::{ParamKind, Statement};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+enum Slot {
+    Empty,
+    Value(Literal),
+    Cell(Cell),
+}
+
+struct CallFrame {
+    closure: Rc<Closure>,
+    ip: usize,
+    base: usize,
+    height: usize,
+    // None for the script itself, which does not appear in tracebacks
+    call_site: Option<Span>,
+}
+
+pub struct Vm {
+    file_path: PathBuf,
+    modules: Modules,
+    eval_main: bool,
+    globals: Scope,
+    stack: Vec<Literal>,
+    locals: Vec<Slot>,
+    frames: Vec<CallFrame>,
+    args: Vec<String>,
+    heap: Heap,
+}
+
+impl Vm {
+    pub fn new(file_path: PathBuf, modules: Modules, eval_main: bool) -> Self {
+        let globals = Rc::new(RefCell::new(HashMap::new()));
+        let mut heap = Heap::default();
+        heap.track(Object::Scope(Rc::downgrade(&globals)));
+
+        Self {
+            file_path,
+            modules,
+            eval_main,
+            globals,
+            stack: vec![],
+            locals: vec![],
+            frames: vec![],
+            args: vec![],
+            heap,
+        }
+    }
+
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    pub fn get_globals(&self) -> HashMap<String, Variable> {
+        self.globals.borrow().clone()
+    }
+
+    pub fn get_var(&self, id: &str) -> Result<Literal> {
+        match self.get_global(id) {
+            Some(literal) => Ok(literal),
+            None => {
+                let error = FlushError::from(format!("The variable `{}` cannot be found!", id));
+
+                Err(self.suggest(id, error))
+            }
+        }
+    }
+
+    pub fn execute(&mut self, statements: Vec<Statement>) -> Result<()> {
+        self.script(statements, false).map(|_| ())
+    }
+
+    // Like `execute`, but a trailing top-level expression is allowed and is
+    // the returned value
+    pub fn eval_input(&mut self, statements: Vec<Statement>) -> Result<Literal> {
+        self.script(statements, true)
+    }
+
+    fn script(&mut self, mut statements: Vec<Statement>, repl: bool) -> Result<Literal> {
+        Resolver::resolve(&mut statements);
+
+        let closure = Rc::new(Closure {
+            function: Rc::new(Compiler::compile(&statements, repl)),
+            upvalues: vec![],
+            globals: self.globals.clone(),
+        });
+
+        self.complete(|vm| {
+            vm.enter(closure, vec![], None);
+            Ok(())
+        })
+    }
+
+    // Runs the frames entered by `start` until they return and pops their
+    // result. On an error, the VM is put back as it was before `start`.
+    fn complete(&mut self, start: impl FnOnce(&mut Self) -> Result<()>) -> Result<Literal> {
+        let (frames, height, locals) = (self.frames.len(), self.stack.len(), self.locals.len());
+
+        let result = start(self).and_then(|()| match self.frames.len() > frames {
+            true => self.run(frames),
+            false => Ok(()),
+        });
+
+        match result {
+            Ok(()) => Ok(self.pop()),
+            Err(error) => {
+                self.frames.truncate(frames);
+                self.stack.truncate(height);
+                self.locals.truncate(locals);
+
+                Err(error)
+            }
+        }
+    }
+
+    fn enter(
+        &mut self,
+        closure: Rc<Closure>,
+        literals: impl IntoIterator<Item = Option<Literal>>,
+        call_site: Option<Span>,
+    ) {
+        let base = self.locals.len();
+
+        for captured in &closure.function.captured {
+            self.locals.push(match captured {
+                true => Slot::Cell(Rc::new(RefCell::new(None))),
+                false => Slot::Empty,
+            });
+        }
+
+        for (slot, literal) in literals.into_iter().enumerate() {
+            if let Some(literal) = literal {
+                self.store(base + slot, literal);
+            }
+        }
+
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            base,
+            height: self.stack.len(),
+            call_site,
+        });
+    }
+
+    // Runs until only `stop` frames are left. The running function only
+    // changes on calls and returns, which are the only instructions that
+    // change the number of frames.
+    fn run(&mut self, stop: usize) -> Result<()> {
+        let mut function = self.frames.last().unwrap().closure.function.clone();
+        let mut depth = self.frames.len();
+
+        loop {
+            // Between two instructions, every value in use is on the stack,
+            // in a local or in a frame
+            if self.heap.due() {
+                self.collect_garbage();
+            }
+
+            let frame = self.frames.last_mut().unwrap();
+            let ip = frame.ip;
+            frame.ip += 1;
+
+            if let Err(error) = self.step(&function, ip) {
+                return Err(self.unwind(error.or_span(&function.spans[ip]), stop));
+            }
+
+            if self.frames.len() != depth {
+                if self.frames.len() == stop {
+                    return Ok(());
+                }
+
+                function = self.frames.last().unwrap().closure.function.clone();
+                depth = self.frames.len();
+            }
+        }
+    }
+
+    fn unwind(&mut self, mut error: FlushError, stop: usize) -> FlushError {
+        while self.frames.len() > stop {
+            let frame = self.frames.pop().unwrap();
+
+            if let Some(call_site) = frame.call_site {
+                error = error.with_frame(Frame {
+                    function: frame.closure.function.name.clone(),
+                    call_site,
+                });
+            }
+        }
+
+        error
+    }
+
+    fn step(&mut self, function: &Function, ip: usize) -> Result<()> {
+        let span = &function.spans[ip];
+
+        match function.code[ip] {
+            Instruction::Constant(index) => {
+                self.stack.push(function.constants[index as usize].clone())
+            }
+            Instruction::Pop => {
+                self.pop();
+            }
+            Instruction::Get(location) => {
+                let literal = self.get(function, location, false)?;
+                self.stack.push(literal);
+            }
+            Instruction::GetFunction(location) => {
+                let literal = self.get(function, location, true)?;
+                self.stack.push(literal);
+            }
+            Instruction::Set(location) => {
+                let literal = self.pop();
+                self.set(function, location, literal)?;
+            }
+            Instruction::Define(location) => {
+                let literal = self.pop();
+                self.define(function, location, literal, span, true)?;
+            }
+            Instruction::Bind(location) => {
+                let literal = self.pop();
+                self.define(function, location, literal, span, false)?;
+            }
+            Instruction::Unique(location) => {
+                if let Some((id, first_span)) = self.find(function, location) {
+                    return Err(Interpreter::already_exists(&id, &first_span));
+                }
+            }
+            Instruction::Enter(start, end) => {
+                let base = self.frame().base;
+
+                for slot in start as usize..end as usize {
+                    self.locals[base + slot] = match function.captured[slot] {
+                        true => Slot::Cell(Rc::new(RefCell::new(None))),
+                        false => Slot::Empty,
+                    };
+                }
+            }
+            Instruction::List(count) => {
+                let elements = self.stack.split_off(self.stack.len() - count as usize);
+                let list = Literal::list(elements.into_iter().map(Box::new).collect());
+
+                self.heap.adopt(&list);
+                self.stack.push(list);
+            }
+            Instruction::Dictionnary(index) => {
+                let keys = &function.keys[index as usize];
+                let values = self.stack.split_off(self.stack.len() - keys.len());
+                let dict = keys
+                    .iter()
+                    .cloned()
+                    .zip(values.into_iter().map(Box::new))
+                    .collect();
+
+                let dict = Literal::dictionnary(dict);
+
+                self.heap.adopt(&dict);
+                self.stack.push(dict);
+            }
+            Instruction::Index => {
+                let index = self.pop();
+                let literal = self.pop();
+
+                self.stack.push(Interpreter::index(literal, index)?);
+            }
+            Instruction::IndexSet => {
+                let index = self.pop();
+                let literal = self.pop();
+                let value = self.pop();
+
+                Interpreter::set_index(literal, index, Box::new(value))?;
+            }
+            Instruction::Binary(op) => {
+                let right = self.pop();
+                let left = self.pop();
+
+                let literal = Literal::binary(&op, left, right)?;
+
+                self.heap.adopt(&literal);
+                self.stack.push(literal);
+            }
+            Instruction::Negate => {
+                let literal = self.pop();
+                self.stack.push(Literal::negate(literal)?);
+            }
+            Instruction::Not => {
+                let literal = self.pop();
+                self.stack
+                    .push(Literal::Boolean(!Interpreter::condition(literal)?));
+            }
+            Instruction::Condition => {
+                let literal = self.pop();
+                self.stack
+                    .push(Literal::Boolean(Interpreter::condition(literal)?));
+            }
+            Instruction::Jump(target) => self.frame().ip = target as usize,
+            Instruction::JumpUnless(target) => {
+                let literal = self.pop();
+
+                if !Interpreter::condition(literal)? {
+                    self.frame().ip = target as usize;
+                }
+            }
+            Instruction::JumpIfDefined(slot, target) => {
+                if self.local(slot).is_some() {
+                    self.frame().ip = target as usize;
+                }
+            }
+            Instruction::Iterate => match self.pop() {
+                Literal::List(list) => {
+                    self.stack.push(Literal::List(list));
+                    self.stack.push(Literal::Int(0));
+                }
+                unexpected => return Err(format!("Cannot iterate over {}", unexpected).into()),
+            },
+            Instruction::Next(target) => {
+                let height = self.stack.len();
+                let index = match &self.stack[height - 1] {
+                    Literal::Int(index) => *index as usize,
+                    _ => unreachable!(),
+                };
+                let element = match &self.stack[height - 2] {
+                    Literal::List(list) => list.borrow().get(index).map(|element| *element.clone()),
+                    _ => unreachable!(),
+                };
+
+                match element {
+                    Some(element) => {
+                        self.stack[height - 1] = Literal::Int(index as i64 + 1);
+                        self.stack.push(element);
+                    }
+                    None => self.frame().ip = target as usize,
+                }
+            }
+            Instruction::Closure(index) => {
+                let closure = Rc::new(self.closure(function.functions[index as usize].clone()));
+
+                for upvalue in &closure.upvalues {
+                    self.heap.track(Object::Cell(Rc::downgrade(upvalue)));
+                }
+
+                self.heap.track(Object::Closure(Rc::downgrade(&closure)));
+                self.stack.push(Literal::Closure(closure));
+            }
+            Instruction::Call(count) => {
+                let literals = self.stack.split_off(self.stack.len() - count as usize);
+                let function = self.pop();
+
+                self.call(function, literals, vec![], span.clone())?;
+            }
+            Instruction::CallNamed(count, index) => {
+                let names = &function.keys[index as usize];
+                let named_literals = self.stack.split_off(self.stack.len() - names.len());
+                let literals = self.stack.split_off(self.stack.len() - count as usize);
+                let function = self.pop();
+
+                self.call(
+                    function,
+                    literals,
+                    names.iter().cloned().zip(named_literals).collect(),
+                    span.clone(),
+                )?;
+            }
+            Instruction::CallBuiltin(index, count) => {
+                let literals = self.stack.split_off(self.stack.len() - count as usize);
+                let literal = BUILTINS[index as usize].1(self, literals)?;
+
+                self.heap.adopt(&literal);
+                self.stack.push(literal);
+            }
+            Instruction::CallMain => {
+                let main = self.pop();
+
+                if !self.eval_main {
+                    self.stack.push(Literal::None);
+                    return Ok(());
+                }
+
+                let args = match &main {
+                    Literal::Closure(closure) => {
+                        match closure.function.params.first().map(|param| &param.kind) {
+                            Some(ParamKind::Rest(_)) => self
+                                .args
+                                .iter()
+                                .map(|arg| Literal::String(arg.as_str().into()))
+                                .collect(),
+                            Some(_) => vec![self.get_args()],
+                            None => vec![],
+                        }
+                    }
+                    _ => vec![],
+                };
+
+                for arg in &args {
+                    self.heap.adopt(arg);
+                }
+
+                self.call(main, args, vec![], span.clone())?;
+            }
+            Instruction::Return => {
+                let literal = self.pop();
+                let frame = self.frames.pop().unwrap();
+
+                self.locals.truncate(frame.base);
+                self.stack.truncate(frame.height);
+                self.stack.push(literal);
+            }
+            Instruction::Load(index) => self.load(&function.names[index as usize], span)?,
+            Instruction::Fail(index) => {
+                let (message, help) = &function.failures[index as usize];
+                let error = FlushError::from(message.clone());
+
+                return Err(match help {
+                    Some(help) => error.with_help(help.clone()),
+                    None => error,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn frame(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn pop(&mut self) -> Literal {
+        self.stack.pop().unwrap()
+    }
+
+    fn globals(&self) -> &Scope {
+        match self.frames.last() {
+            Some(frame) => &frame.closure.globals,
+            None => &self.globals,
+        }
+    }
+
+    fn closure(&self, function: Rc<Function>) -> Closure {
+        let frame = self.frames.last().unwrap();
+        let upvalues = function
+            .captures
+            .iter()
+            .map(|(capture, _, _)| match *capture {
+                Capture::Local(slot) => match &self.locals[frame.base + slot as usize] {
+                    Slot::Cell(cell) => cell.clone(),
+                    _ => unreachable!(),
+                },
+                Capture::Upvalue(index) => frame.closure.upvalues[index as usize].clone(),
+            })
+            .collect();
+
+        Closure {
+            function,
+            upvalues,
+            globals: frame.closure.globals.clone(),
+        }
+    }
+
+    fn call(
+        &mut self,
+        function: Literal,
+        literals: Vec<Literal>,
+        named_literals: Vec<(String, Literal)>,
+        call_site: Span,
+    ) -> Result<()> {
+        let closure = match function {
+            Literal::Closure(closure) => closure,
+            Literal::Host(host) => {
+                if !named_literals.is_empty() {
+                    return Err(format!("`{}` does not accept named arguments!", host.name).into());
+                }
+
+                let literal = host.call(self, literals)?;
+                self.stack.push(literal);
+
+                return Ok(());
+            }
+            unexpected => return Err(format!("`{}` is not a function!", unexpected).into()),
+        };
+
+        if self.frames.len() > MAX_CALL_DEPTH {
+            return Err(FlushError::from(format!(
+                "Maximum call depth of `{}` exceeded while calling `{}`!",
+                MAX_CALL_DEPTH, closure.function.name
+            ))
+            .with_help("check that the recursion has a reachable base case"));
+        }
+
+        let params = &closure.function.params;
+        let positional = named_literals.is_empty()
+            && literals.len() == params.len()
+            && !matches!(
+                params.last().map(|param| &param.kind),
+                Some(ParamKind::Rest(_))
+            );
+
+        if positional {
+            self.enter(closure, literals.into_iter().map(Some), Some(call_site));
+            return Ok(());
+        }
+
+        let literals = Interpreter::bind(&closure.function.name, params, literals, named_literals)?;
+
+        if let (Some(ParamKind::Rest(_)), Some(Some(rest))) =
+            (params.last().map(|param| &param.kind), literals.last())
+        {
+            self.heap.adopt(rest);
+        }
+
+        self.enter(closure, literals, Some(call_site));
+
+        Ok(())
+    }
+
+    fn load(&mut self, raw_file_path: &str, span: &Span) -> Result<()> {
+        if raw_file_path == self.file_path.to_string_lossy() {
+            return Ok(());
+        }
+
+        match run(raw_file_path, &self.modules, false, Backend::Vm) {
+            Ok(Some(exports)) => {
+                for variable in exports.values() {
+                    self.heap.adopt(&variable.literal);
+                }
+
+                self.globals().borrow_mut().extend(exports)
+            }
+            Ok(None) => {}
+            Err(error) => {
+                return Err(error.with_frame(Frame {
+                    function: "<module>".to_string(),
+                    call_site: span.clone(),
+                }))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn local(&self, slot: u32) -> Option<Literal> {
+        match &self.locals[self.frames.last().unwrap().base + slot as usize] {
+            Slot::Empty => None,
+            Slot::Value(literal) => Some(literal.clone()),
+            Slot::Cell(cell) => cell.borrow().clone(),
+        }
+    }
+
+    fn upvalue(&self, index: u32) -> Option<Literal> {
+        self.frames.last().unwrap().closure.upvalues[index as usize]
+            .borrow()
+            .clone()
+    }
+
+    fn store(&mut self, slot: usize, literal: Literal) {
+        match &mut self.locals[slot] {
+            Slot::Cell(cell) => *cell.borrow_mut() = Some(literal),
+            slot => *slot = Slot::Value(literal),
+        }
+    }
+
+    fn declaration(function: &Function, location: Location) -> (&str, Span) {
+        match location {
+            Location::Local(slot) => {
+                let (id, span) = &function.locals[slot as usize];
+                (id, span.clone())
+            }
+            Location::Upvalue(index) => {
+                let (_, id, span) = &function.captures[index as usize];
+                (id, span.clone())
+            }
+            Location::Global(index) => (&function.names[index as usize], Span::default()),
+        }
+    }
+
+    fn lookup(&self, location: Location) -> Option<Literal> {
+        match location {
+            Location::Local(slot) => self.local(slot),
+            Location::Upvalue(index) => self.upvalue(index),
+            Location::Global(_) => None,
+        }
+    }
+
+    fn assign(&mut self, location: Location, literal: Literal) {
+        let frame = self.frames.last().unwrap();
+
+        match location {
+            Location::Local(slot) => {
+                let slot = frame.base + slot as usize;
+                self.store(slot, literal);
+            }
+            Location::Upvalue(index) => {
+                *frame.closure.upvalues[index as usize].borrow_mut() = Some(literal)
+            }
+            Location::Global(_) => unreachable!(),
+        }
+    }
+
+    fn find(&self, function: &Function, location: Location) -> Option<(String, Span)> {
+        let (id, span) = Self::declaration(function, location);
+
+        if self.lookup(location).is_some() {
+            return Some((id.to_string(), span));
+        }
+
+        self.globals()
+            .borrow()
+            .get(id)
+            .map(|variable| (id.to_string(), variable.span.clone()))
+    }
+
+    fn get(&self, function: &Function, location: Location, callee: bool) -> Result<Literal> {
+        if let Some(literal) = self.lookup(location) {
+            return Ok(literal);
+        }
+
+        let (id, _) = Self::declaration(function, location);
+
+        if let Some(variable) = self.globals().borrow().get(id) {
+            return Ok(variable.literal.clone());
+        }
+
+        let error = match callee {
+            true => FlushError::from(format!("Undefined function `{}`!", id)),
+            false => FlushError::from(format!("The variable `{}` cannot be found!", id)),
+        };
+
+        Err(self.suggest(id, error))
+    }
+
+    fn set(&mut self, function: &Function, location: Location, literal: Literal) -> Result<()> {
+        if self.lookup(location).is_some() {
+            self.assign(location, literal);
+            return Ok(());
+        }
+
+        let (id, _) = Self::declaration(function, location);
+
+        if let Some(variable) = self.globals().borrow_mut().get_mut(id) {
+            variable.literal = literal;
+            return Ok(());
+        }
+
+        let error = FlushError::from(format!("The variable `{}` cannot be found!", id));
+
+        Err(self.suggest(id, error))
+    }
+
+    fn define(
+        &mut self,
+        function: &Function,
+        location: Location,
+        literal: Literal,
+        span: &Span,
+        checked: bool,
+    ) -> Result<()> {
+        if let Location::Global(index) = location {
+            let id = &function.names[index as usize];
+            let mut globals = self.globals().borrow_mut();
+
+            match globals.get(id) {
+                Some(variable) if checked => {
+                    return Err(Interpreter::already_exists(id, &variable.span))
+                }
+                _ => {}
+            }
+
+            globals.insert(
+                id.clone(),
+                Variable {
+                    literal,
+                    span: span.clone(),
+                },
+            );
+
+            return Ok(());
+        }
+
+        if checked && self.lookup(location).is_some() {
+            let (id, first_span) = Self::declaration(function, location);
+
+            return Err(Interpreter::already_exists(id, &first_span));
+        }
+
+        self.assign(location, literal);
+
+        Ok(())
+    }
+
+    fn collect_garbage(&mut self) -> usize {
+        let mut roots = vec![Object::Scope(Rc::downgrade(&self.globals))];
+
+        roots.extend(self.stack.iter().filter_map(Object::of));
+        roots.extend(self.locals.iter().filter_map(|slot| match slot {
+            Slot::Empty => None,
+            Slot::Value(literal) => Object::of(literal),
+            Slot::Cell(cell) => Some(Object::Cell(Rc::downgrade(cell))),
+        }));
+        roots.extend(
+            self.frames
+                .iter()
+                .map(|frame| Object::Closure(Rc::downgrade(&frame.closure))),
+        );
+        roots.extend(self.modules.borrow().roots());
+
+        self.heap.collect(roots)
+    }
+
+    fn suggest(&self, id: &str, error: FlushError) -> FlushError {
+        let globals = self.globals().borrow();
+        let names = globals
+            .keys()
+            .map(|name| name.as_str())
+            .chain(builtins::names());
+
+        match similar_name(id, names) {
+            Some(name) => error.with_help(format!("a similar name exists: `{}`", name)),
+            None => error,
+        }
+    }
+}
+
+impl Runtime for Vm {
+    fn heap(&mut self) -> &mut Heap {
+        &mut self.heap
+    }
+
+    fn collect(&mut self) -> usize {
+        self.collect_garbage()
+    }
+
+    fn get_args(&self) -> Literal {
+        Literal::list(
+            self.args
+                .iter()
+                .map(|arg| Box::new(Literal::String(arg.as_str().into())))
+                .collect(),
+        )
+    }
+
+    fn call(&mut self, function: Literal, args: Vec<Literal>) -> Result<Literal> {
+        self.complete(|vm| vm.call(function, args, vec![], Span::default()))
+    }
+
+    fn get_global(&self, id: &str) -> Option<Literal> {
+        self.globals
+            .borrow()
+            .get(id)
+            .map(|variable| variable.literal.clone())
+    }
+
+    fn set_global(&mut self, id: String, literal: Literal) {
+        self.heap.adopt(&literal);
+
+        let mut globals = self.globals.borrow_mut();
+
+        match globals.get_mut(&id) {
+            Some(variable) => variable.literal = literal,
+            None => {
+                globals.insert(
+                    id,
+                    Variable {
+                        literal,
+                        span: Span::default(),
+                    },
+                );
+            }
+        }
+    }
+}
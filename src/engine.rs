@@ -0,0 +1,170 @@
+use crate::compiling::vm::Vm;
+use crate::error::{FlushError, Result, Span};
+use crate::flush::{parse, read_file};
+use crate::interpreting::builtins::{self, Runtime};
+use crate::interpreting::host::{Ctx, HostFunction};
+use crate::interpreting::interpreter::Interpreter;
+use crate::interpreting::modules::ModuleRegistry;
+use crate::interpreting::typing::Literal;
+use crate::parsing::typing::Resolution;
+use crate::value::Value;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+enum Executor {
+    Interpreter(Interpreter),
+    Vm(Vm),
+}
+
+pub struct Engine {
+    executor: Executor,
+    args: Vec<String>,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self {
+            executor: Executor::Interpreter(Interpreter::repl(
+                PathBuf::from("<engine>"),
+                ModuleRegistry::shared(),
+            )),
+            args: vec![],
+        }
+    }
+
+    // Runs everything on the bytecode virtual machine instead of the interpreter
+    pub fn with_vm(mut self) -> Self {
+        let file_path = PathBuf::from("<engine>");
+        let modules = ModuleRegistry::shared();
+        modules.borrow_mut().start(file_path.clone());
+
+        let vm = Vm::new(file_path, modules, false).with_args(self.args.clone());
+        self.executor = Executor::Vm(vm);
+        self
+    }
+
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        match &mut self.executor {
+            Executor::Interpreter(interpreter) => interpreter.set_args(args.clone()),
+            Executor::Vm(vm) => vm.set_args(args.clone()),
+        }
+
+        self.args = args;
+        self
+    }
+
+    fn runtime(&self) -> &dyn Runtime {
+        match &self.executor {
+            Executor::Interpreter(interpreter) => interpreter,
+            Executor::Vm(vm) => vm,
+        }
+    }
+
+    fn runtime_mut(&mut self) -> &mut dyn Runtime {
+        match &mut self.executor {
+            Executor::Interpreter(interpreter) => interpreter,
+            Executor::Vm(vm) => vm,
+        }
+    }
+
+    fn eval_source(&mut self, source: &str, file_path: PathBuf) -> Result<Value> {
+        let statements = parse(source, file_path)?;
+
+        let literal = match &mut self.executor {
+            Executor::Interpreter(interpreter) => {
+                interpreter.eval_input(statements)?.unwrap_or(Literal::None)
+            }
+            Executor::Vm(vm) => vm.eval_input(statements)?,
+        };
+
+        Ok(self.runtime_mut().heap().export(literal))
+    }
+
+    pub fn eval(&mut self, source: &str) -> Result<Value> {
+        self.eval_source(source, PathBuf::from("<eval>"))
+    }
+
+    pub fn eval_file(&mut self, file_path: impl AsRef<Path>) -> Result<Value> {
+        let (file_path, content) = read_file(&file_path.as_ref().to_string_lossy())?;
+
+        self.eval_source(&content, file_path)
+    }
+
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value> {
+        let heap = self.runtime_mut().heap();
+        let literals = args.iter().map(|value| heap.import(value)).collect();
+
+        let literal = match &mut self.executor {
+            Executor::Interpreter(interpreter) => {
+                let function = interpreter.get_var(name, &Resolution::Global)?;
+
+                interpreter.call_function(function, literals, vec![], Span::default())?
+            }
+            Executor::Vm(vm) => {
+                let function = vm.get_var(name)?;
+
+                Runtime::call(vm, function, literals)?
+            }
+        };
+
+        Ok(self.runtime_mut().heap().export(literal))
+    }
+
+    // Builtins and globals the scripts already define keep their meaning: a
+    // host function may only take a name that is still free
+    pub fn register<F>(&mut self, name: &str, function: F) -> Result<()>
+    where
+        F: Fn(&mut Ctx, &[Value]) -> Result<Value> + 'static,
+    {
+        if builtins::index(name).is_some() {
+            return Err(FlushError::from(format!(
+                "Cannot register `{}`, a builtin function has this name!",
+                name
+            )));
+        }
+
+        if self.runtime().get_global(name).is_some() {
+            return Err(FlushError::from(format!(
+                "Cannot register `{}`, a global with this name already exists!",
+                name
+            )));
+        }
+
+        let host = HostFunction {
+            name: name.to_string(),
+            function: Box::new(function),
+        };
+
+        self.runtime_mut()
+            .set_global(name.to_string(), Literal::Host(Rc::new(host)));
+
+        Ok(())
+    }
+
+    // The returned value is kept alive by the collector until it is dropped
+    pub fn get(&mut self, name: &str) -> Option<Value> {
+        let literal = self.runtime().get_global(name)?;
+
+        Some(self.runtime_mut().heap().export(literal))
+    }
+
+    pub fn set(&mut self, name: &str, value: Value) {
+        let literal = self.runtime_mut().heap().import(&value);
+
+        self.runtime_mut().set_global(name.to_string(), literal);
+    }
+}
+
+// The globals and the functions defining them reference each other, so the
+// engine's objects are collected with only the values the host kept as roots
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.runtime_mut().heap().collect(vec![]);
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
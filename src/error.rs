@@ -1,20 +1,411 @@
 use ansi_term::Colour::{Blue, Red, Yellow};
-use std::{fmt, path::PathBuf};
+use ansi_term::Style;
+use std::io::IsTerminal;
+use std::ops::{Deref, DerefMut};
+use std::{env, fmt, path::PathBuf, rc::Rc};
 
+#[derive(Default, PartialEq)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl SourceFile {
+    pub fn new(path: PathBuf, content: &str) -> Self {
+        Self {
+            path,
+            content: content.to_string(),
+        }
+    }
+
+    pub fn line(&self, line: usize) -> Option<&str> {
+        self.content.lines().nth(line.checked_sub(1)?)
+    }
+}
+
+impl fmt::Debug for SourceFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.path)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Span {
+    pub file: Rc<SourceFile>,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    pub fn is_unknown(&self) -> bool {
+        self.line == 0
+    }
+
+    pub fn to(&self, end: &Span) -> Span {
+        let mut span = self.clone();
+
+        if end.line == self.line && end.column + end.length > self.column {
+            span.length = end.column + end.length - self.column;
+        }
+
+        span.end_line = end.end_line;
+        span.end_column = end.end_column;
+
+        span
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub function: String,
+    pub call_site: Span,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+// Boxed so that a `Result` stays as small as its `Ok` value
 #[derive(Debug)]
-pub struct FlushError(pub PathBuf, pub usize, pub String);
+pub struct FlushError(Box<Diagnostic>);
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub code: Option<String>,
+    pub span: Span,
+    pub message: String,
+    pub labels: Vec<(Span, String)>,
+    pub help: Option<String>,
+    pub trace: Vec<Frame>,
+    pub exit_code: Option<i32>,
+}
 
 pub type Result<T> = std::result::Result<T, FlushError>;
 
+impl Deref for FlushError {
+    type Target = Diagnostic;
+
+    fn deref(&self) -> &Diagnostic {
+        &self.0
+    }
+}
+
+impl DerefMut for FlushError {
+    fn deref_mut(&mut self) -> &mut Diagnostic {
+        &mut self.0
+    }
+}
+
+impl FlushError {
+    pub fn new(span: Span, message: impl ToString) -> Self {
+        Self(Box::new(Diagnostic {
+            level: Level::Error,
+            code: None,
+            span,
+            message: message.to_string(),
+            labels: vec![],
+            help: None,
+            trace: vec![],
+            exit_code: None,
+        }))
+    }
+
+    pub fn exit(code: i32) -> Self {
+        let mut error = FlushError::from(format!("Exited with code {}", code));
+        error.exit_code = Some(code);
+
+        error
+    }
+
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn with_code(mut self, code: impl ToString) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, label: impl ToString) -> Self {
+        self.labels.push((span, label.to_string()));
+        self
+    }
+
+    pub fn with_help(mut self, help: impl ToString) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    pub fn with_frame(mut self, frame: Frame) -> Self {
+        self.trace.push(frame);
+        self
+    }
+
+    pub fn or_span(mut self, span: &Span) -> Self {
+        if self.span.is_unknown() {
+            self.span = span.clone();
+        }
+
+        self
+    }
+}
+
+impl From<String> for FlushError {
+    fn from(message: String) -> Self {
+        FlushError::new(Span::default(), message)
+    }
+}
+
+impl From<&str> for FlushError {
+    fn from(message: &str) -> Self {
+        FlushError::new(Span::default(), message)
+    }
+}
+
+pub fn colors_enabled() -> bool {
+    env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal()
+}
+
+pub fn style(style: Style) -> Style {
+    if colors_enabled() {
+        style
+    } else {
+        Style::new()
+    }
+}
+
+pub fn similar_name<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= name.chars().count().max(3).div_ceil(3))
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous[j] + (left_char != *right_char) as usize;
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[right.len()]
+}
+
+const TRACE_EDGE: usize = 10;
+
+impl FlushError {
+    fn colour(&self) -> Style {
+        match self.level {
+            Level::Error => style(Red.bold()),
+            Level::Warning => style(Yellow.bold()),
+        }
+    }
+
+    fn write_traceback(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut entries: Vec<(&Span, &str)> = vec![];
+        let mut caller = "<module>";
+
+        for frame in self.trace.iter().rev() {
+            if !frame.call_site.is_unknown() {
+                entries.push((&frame.call_site, caller));
+            }
+
+            caller = &frame.function;
+        }
+
+        if !self.span.is_unknown() {
+            entries.push((&self.span, caller));
+        }
+
+        if self.trace.is_empty() || entries.is_empty() {
+            return Ok(());
+        }
+
+        let elided = entries.len().saturating_sub(2 * TRACE_EDGE);
+
+        writeln!(f, "Traceback (most recent call last):")?;
+
+        for (index, (span, function)) in entries.into_iter().enumerate() {
+            if elided > 0 && index == TRACE_EDGE {
+                writeln!(f, "  ... {} frames elided ...", elided)?;
+            }
+
+            if elided > 0 && index >= TRACE_EDGE && index < TRACE_EDGE + elided {
+                continue;
+            }
+
+            writeln!(
+                f,
+                "  File \"{}\", line {}, in {}",
+                style(Blue.normal()).paint(span.file.path.to_string_lossy()),
+                span.line,
+                function
+            )?;
+
+            if let Some(source) = span.file.line(span.line) {
+                writeln!(f, "    {}", source.trim())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_snippet(
+        &self,
+        f: &mut fmt::Formatter,
+        labels: &[(&Span, &str, bool)],
+        width: usize,
+    ) -> fmt::Result {
+        let gutter = style(Blue.bold());
+        let mut previous_line = 0;
+
+        write!(f, "\n{:width$} {}", "", gutter.paint("|"), width = width)?;
+
+        for (span, label, primary) in labels {
+            let source = span.file.line(span.line).unwrap_or_default();
+
+            if previous_line != 0 && span.line > previous_line + 1 {
+                write!(f, "\n{}", gutter.paint("..."))?;
+            }
+
+            if span.line != previous_line {
+                write!(
+                    f,
+                    "\n{} {}",
+                    gutter.paint(format!("{:>width$} |", span.line, width = width)),
+                    source
+                )?;
+                previous_line = span.line;
+            }
+
+            let padding: String = source
+                .chars()
+                .take(span.column.saturating_sub(1))
+                .map(|character| if character == '\t' { '\t' } else { ' ' })
+                .collect();
+            let remaining = source.chars().count().saturating_sub(padding.len());
+            let length = span.length.min(remaining).max(1);
+            let (marker, colour) = if *primary {
+                ("^", style(Red.bold()))
+            } else {
+                ("-", gutter)
+            };
+
+            write!(
+                f,
+                "\n{:width$} {} {}{}",
+                "",
+                gutter.paint("|"),
+                padding,
+                colour.paint(format!("{} {}", marker.repeat(length), label).trim_end()),
+                width = width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 impl fmt::Display for FlushError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_traceback(f)?;
+
+        let level = match (self.level, &self.code) {
+            (Level::Error, None) => "error".to_string(),
+            (Level::Warning, None) => "warning".to_string(),
+            (Level::Error, Some(code)) => format!("error[{}]", code),
+            (Level::Warning, Some(code)) => format!("warning[{}]", code),
+        };
+
         write!(
             f,
-            "{}: {}\n=> {}:{}",
-            Red.paint("[error]"),
-            self.2,
-            Blue.paint(self.0.to_str().unwrap()),
-            Yellow.paint(self.1.to_string()),
-        )
+            "{}{} {}",
+            self.colour().paint(level),
+            style(Style::new().bold()).paint(":"),
+            style(Style::new().bold()).paint(&self.message)
+        )?;
+
+        let mut labels: Vec<(&Span, &str, bool)> = vec![];
+
+        if !self.span.is_unknown() {
+            labels.push((&self.span, "", true));
+        }
+
+        for (span, label) in &self.labels {
+            if !span.is_unknown() {
+                labels.push((span, label, false));
+            }
+        }
+
+        let width = labels
+            .iter()
+            .map(|(span, _, _)| span.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = style(Blue.bold());
+
+        if let Some((span, _, _)) = labels.first() {
+            let mut files: Vec<&Rc<SourceFile>> = vec![];
+
+            for (span, _, _) in &labels {
+                if !files.iter().any(|file| Rc::ptr_eq(file, &span.file)) {
+                    files.push(&span.file);
+                }
+            }
+
+            for (index, file) in files.into_iter().enumerate() {
+                let mut file_labels: Vec<(&Span, &str, bool)> = labels
+                    .iter()
+                    .filter(|(span, _, _)| Rc::ptr_eq(&span.file, file))
+                    .cloned()
+                    .collect();
+                let first = if index == 0 { *span } else { file_labels[0].0 };
+
+                file_labels.sort_by_key(|(span, _, primary)| (span.line, !primary, span.column));
+
+                write!(
+                    f,
+                    "\n{:width$}{} {}:{}:{}",
+                    "",
+                    gutter.paint(if index == 0 { "-->" } else { ":::" }),
+                    file.path.to_string_lossy(),
+                    first.line,
+                    first.column,
+                    width = width
+                )?;
+                self.write_snippet(f, &file_labels, width)?;
+            }
+        }
+
+        if let Some(help) = &self.help {
+            write!(
+                f,
+                "\n{:width$} {} {}: {}",
+                "",
+                gutter.paint("="),
+                style(Yellow.bold()).paint("help"),
+                help,
+                width = width
+            )?;
+        }
+
+        Ok(())
     }
 }
@@ -1,31 +1,32 @@
+use crate::compiling::vm::Vm;
+use crate::error::{style, Result};
 use crate::interpreting::interpreter::Interpreter;
-use crate::interpreting::typing::Literal;
+use crate::interpreting::modules::Modules;
+use crate::interpreting::typing::Variable;
 use crate::lexing::lexer::Lexer;
 use crate::parsing::parser::Parser;
-use crate::parsing::typing::Statement;
-use ansi_term::Colour::{Blue, Red, Yellow};
+use crate::parsing::typing::{Statement, StatementKind};
+use ansi_term::Colour::{Blue, Yellow};
 use std::collections::HashMap;
 use std::fs::{canonicalize, read_to_string};
 use std::path::PathBuf;
 
-pub fn process_file_path(raw_file_path: &str) -> Result<PathBuf, String> {
+#[derive(Clone, Copy, PartialEq)]
+pub enum Backend {
+    Interpreter,
+    Vm,
+}
+
+pub fn process_file_path(raw_file_path: &str) -> Result<PathBuf> {
     let file_path = PathBuf::from(raw_file_path);
     let str_file_path = file_path.to_str().unwrap_or("<not valid unicode>");
 
     if !file_path.exists() {
-        return Err(format!(
-            "{}: `{}` does not exist!",
-            Red.paint("[error]"),
-            str_file_path
-        ));
+        return Err(format!("`{}` does not exist!", str_file_path).into());
     }
 
     if !file_path.is_file() {
-        return Err(format!(
-            "{}: `{}` is not a file!",
-            Red.paint("[error]"),
-            str_file_path
-        ));
+        return Err(format!("`{}` is not a file!", str_file_path).into());
     }
 
     let absolute_path = canonicalize(file_path).unwrap();
@@ -33,53 +34,68 @@ pub fn process_file_path(raw_file_path: &str) -> Result<PathBuf, String> {
     Ok(absolute_path)
 }
 
+pub fn read_file(raw_file_path: &str) -> Result<(PathBuf, String)> {
+    let file_path = process_file_path(raw_file_path)?;
+
+    match read_to_string(&file_path) {
+        Ok(content) => Ok((file_path, content)),
+        Err(e) => Err(format!("Could not open file: {}", e).into()),
+    }
+}
+
+pub fn parse(content: &str, file_path: PathBuf) -> Result<Vec<Statement>> {
+    let mut lexer = Lexer::new(content, file_path);
+    let tokens = lexer.tokenize()?;
+
+    Parser::new(tokens).parse()
+}
+
 pub fn run(
     raw_file_path: &str,
-    cache: &mut Vec<PathBuf>,
-) -> Result<Option<HashMap<String, Literal>>, String> {
+    modules: &Modules,
+    eval_main: bool,
+    backend: Backend,
+) -> Result<Option<HashMap<String, Variable>>> {
     let file_path = process_file_path(raw_file_path)?;
 
-    if cache.contains(&file_path) {
+    if let Some(exports) = modules.borrow().exports(&file_path) {
+        return Ok(Some(exports));
+    }
+
+    if modules.borrow().is_loading(&file_path) {
         return Ok(None);
     }
 
-    let file_content = match read_to_string(file_path.clone()) {
-        Ok(content) => content,
-        Err(e) => {
-            return Err(format!(
-                "{}: Could not open file: {}",
-                Red.paint("[error]"),
-                e
-            ))
-        }
-    };
+    let (file_path, content) = read_file(raw_file_path)?;
 
-    let mut lexer = Lexer::new(&file_content, file_path.clone());
-    let tokens = match lexer.tokenize() {
-        Ok(tokens) => tokens,
-        Err(e) => return Err(e.to_string()),
-    };
+    run_source(&content, file_path, modules, eval_main, vec![], backend).map(Some)
+}
 
-    let mut parser = Parser::new(tokens, file_path.clone());
-    let statements = match parser.parse() {
-        Ok(statements) => statements,
-        Err(e) => return Err(e.to_string()),
-    };
+pub fn run_source(
+    content: &str,
+    file_path: PathBuf,
+    modules: &Modules,
+    eval_main: bool,
+    args: Vec<String>,
+    backend: Backend,
+) -> Result<HashMap<String, Variable>> {
+    let statements = parse(content, file_path.clone())?;
 
-    cache.push(file_path.clone());
+    modules.borrow_mut().start(file_path.clone());
 
     let mut new_statements: Vec<Statement> = vec![];
 
-    for statement in statements.clone() {
-        if let Statement::Load(raw_path) = statement.clone() {
-            let absolute_path = process_file_path(&raw_path)?;
+    for statement in statements {
+        if let StatementKind::Load(raw_path) = &statement.kind {
+            let absolute_path =
+                process_file_path(raw_path).map_err(|error| error.or_span(&statement.span))?;
 
-            if absolute_path == file_path || cache.contains(&absolute_path) {
-                println!(
+            if modules.borrow().is_loading(&absolute_path) {
+                eprintln!(
                     "{}: Detected cycle import: {} is importing {} mutually",
-                    Yellow.paint("[warning]"),
-                    Blue.paint(file_path.to_string_lossy()),
-                    Blue.paint(absolute_path.to_string_lossy())
+                    style(Yellow.bold()).paint("warning"),
+                    style(Blue.normal()).paint(file_path.to_string_lossy()),
+                    style(Blue.normal()).paint(absolute_path.to_string_lossy())
                 );
                 continue;
             }
@@ -88,12 +104,34 @@ pub fn run(
         new_statements.push(statement);
     }
 
-    let mut interpreter =
-        Interpreter::new(new_statements, file_path.clone(), cache, cache.len() == 1);
+    let result = match backend {
+        Backend::Interpreter => {
+            let mut interpreter = Interpreter::new(
+                new_statements,
+                file_path.clone(),
+                modules.clone(),
+                eval_main,
+            )
+            .with_args(args);
+
+            interpreter.interpret().map(|_| interpreter.get_stack())
+        }
+        Backend::Vm => {
+            let mut vm = Vm::new(file_path.clone(), modules.clone(), eval_main).with_args(args);
 
-    if let Err(e) = interpreter.interpret() {
-        return Err(format!("{}: {}", Red.paint("[error]"), e));
-    }
+            vm.execute(new_statements).map(|_| vm.get_globals())
+        }
+    };
+
+    let globals = match result {
+        Ok(globals) => globals,
+        Err(error) => {
+            modules.borrow_mut().forget(&file_path);
+            return Err(error);
+        }
+    };
+
+    modules.borrow_mut().finish(file_path, globals.clone());
 
-    Ok(Some(interpreter.get_stack()))
+    Ok(globals)
 }
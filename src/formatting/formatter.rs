@@ -0,0 +1,514 @@
+use crate::error::{Result, Span};
+use crate::lexing::lexer::Lexer;
+use crate::lexing::typing::{Token, TokenKind};
+use crate::parsing::parser::Parser;
+use crate::parsing::typing::*;
+use std::path::PathBuf;
+
+const INDENT: &str = "    ";
+
+struct Comment {
+    line: usize,
+    text: String,
+    own_line: bool,
+}
+
+pub struct Formatter<'a> {
+    lines: Vec<&'a str>,
+    tokens: Vec<Token>,
+    comments: Vec<Comment>,
+    next_comment: usize,
+    output: String,
+    indent: usize,
+    at_block_start: bool,
+}
+
+impl<'a> Formatter<'a> {
+    pub fn new(content: &'a str, tokens: Vec<Token>) -> Self {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut code = vec![];
+        let mut comments = vec![];
+
+        for token in tokens {
+            match token.kind {
+                TokenKind::Comment(text) => comments.push(Comment {
+                    line: token.span.line,
+                    text: text.trim_end().to_string(),
+                    own_line: lines[token.span.line - 1].trim_start().starts_with('#'),
+                }),
+                _ => code.push(token),
+            }
+        }
+
+        Self {
+            lines,
+            tokens: code,
+            comments,
+            next_comment: 0,
+            output: String::new(),
+            indent: 0,
+            at_block_start: true,
+        }
+    }
+
+    fn token_at(&self, span: &Span) -> usize {
+        self.tokens.partition_point(|token| {
+            (token.span.line, token.span.column) < (span.line, span.column)
+        })
+    }
+
+    fn find(&self, start: usize, kind: &TokenKind) -> usize {
+        start
+            + self.tokens[start..]
+                .iter()
+                .position(|token| &token.kind == kind)
+                .unwrap()
+    }
+
+    fn matching(&self, open: usize) -> usize {
+        let mut depth = 0;
+
+        for (index, token) in self.tokens.iter().enumerate().skip(open) {
+            match token.kind {
+                TokenKind::LParen | TokenKind::LBrace | TokenKind::LBracket => depth += 1,
+                TokenKind::RParen | TokenKind::RBrace | TokenKind::RBracket => depth -= 1,
+                _ => {}
+            }
+
+            if depth == 0 {
+                return index;
+            }
+        }
+
+        unreachable!()
+    }
+
+    fn line(&self, index: usize) -> usize {
+        self.tokens[index].span.line
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn write_indent(&mut self) {
+        self.output.push_str(&INDENT.repeat(self.indent));
+    }
+
+    fn separate(&mut self, line: usize) {
+        let blank_before = line >= 2 && self.lines[line - 2].trim().is_empty();
+
+        if blank_before && !self.at_block_start {
+            self.output.push('\n');
+        }
+
+        self.at_block_start = false;
+    }
+
+    fn has_comment_before(&self, line: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.line < line)
+    }
+
+    fn leading_comments(&mut self, line: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.line >= line {
+                break;
+            }
+
+            let (comment_line, text) = (comment.line, format!("#{}", comment.text));
+
+            self.next_comment += 1;
+            self.separate(comment_line);
+            self.write_indent();
+            self.write(&text);
+            self.output.push('\n');
+        }
+    }
+
+    fn trailing_comment(&mut self, line: usize) {
+        if let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.line == line && !comment.own_line {
+                let text = format!(" #{}", comment.text);
+
+                self.next_comment += 1;
+                self.write(&text);
+            }
+        }
+    }
+
+    fn block(&mut self, statements: &[&Statement], open: usize) -> usize {
+        let close = self.matching(open);
+        let (open_line, close_line) = (self.line(open), self.line(close));
+
+        if statements.is_empty() && !self.has_comment_before(close_line) {
+            self.write("{}");
+            return close;
+        }
+
+        self.write("{");
+        self.trailing_comment(open_line);
+        self.output.push('\n');
+        self.indent += 1;
+        self.at_block_start = true;
+
+        for statement in statements {
+            self.statement(statement);
+        }
+
+        self.leading_comments(close_line);
+        self.indent -= 1;
+        self.write_indent();
+        self.write("}");
+
+        close
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        self.leading_comments(statement.span.line);
+        self.separate(statement.span.line);
+        self.write_indent();
+
+        let start = self.token_at(&statement.span);
+        let end_line = match &statement.kind {
+            StatementKind::If(condition, if_body, else_body) => {
+                self.if_statement(condition, if_body, else_body, start)
+            }
+            StatementKind::FuncDef(id, params, body, _) => {
+                self.write(&format!("def {}", id));
+                let close = self.function(params, body, start + 2);
+                self.line(close)
+            }
+            StatementKind::While(condition, body) => {
+                self.write("while (");
+                self.expr(condition);
+                self.write(") ");
+                let close = self.body(body, start + 1);
+                self.line(close)
+            }
+            StatementKind::For(id, _, iterator, body, _) => {
+                self.write(&format!("for ({} in ", id));
+                self.expr(iterator);
+                self.write(") ");
+                let close = self.body(body, start + 1);
+                self.line(close)
+            }
+            _ => {
+                self.simple_statement(statement);
+                statement.span.end_line
+            }
+        };
+
+        self.trailing_comment(end_line);
+        self.output.push('\n');
+    }
+
+    fn simple_statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::VarDef(id, expr, _) => {
+                self.write(&format!("def {} = ", id));
+                self.expr(expr);
+            }
+            StatementKind::VarSet(id, expr, _) => {
+                self.write(&format!("{} = ", id));
+                self.expr(expr);
+            }
+            StatementKind::IndexSet(target, index, value) => {
+                self.postfix_target(target);
+                self.write("[");
+                self.expr(index);
+                self.write("] = ");
+                self.expr(value);
+            }
+            StatementKind::Return(expr) => {
+                self.write("return ");
+                self.expr(expr);
+            }
+            StatementKind::Break => self.write("break"),
+            StatementKind::Continue => self.write("continue"),
+            StatementKind::Load(path) => self.write(&format!("load \"{}\"", path)),
+            StatementKind::Expr(expr) => self.expr(expr),
+            StatementKind::If(..) | StatementKind::FuncDef(..) => unreachable!(),
+            StatementKind::While(..) | StatementKind::For(..) => unreachable!(),
+        }
+    }
+
+    fn body(&mut self, statements: &[Box<Statement>], paren: usize) -> usize {
+        let statements: Vec<&Statement> = statements.iter().map(|statement| &**statement).collect();
+        let open = self.matching(paren) + 1;
+
+        self.block(&statements, open)
+    }
+
+    fn if_statement(
+        &mut self,
+        condition: &Expr,
+        if_body: &[Box<Statement>],
+        else_body: &[Box<Statement>],
+        start: usize,
+    ) -> usize {
+        self.write("if (");
+        self.expr(condition);
+        self.write(") ");
+
+        let close = self.body(if_body, start + 1);
+
+        if self.tokens.get(close + 1).map(|token| &token.kind) != Some(&TokenKind::Else) {
+            return self.line(close);
+        }
+
+        self.write(" else ");
+
+        if self.tokens[close + 2].kind == TokenKind::If {
+            if let StatementKind::If(condition, if_body, else_body) = &else_body[0].kind {
+                return self.if_statement(condition, if_body, else_body, close + 2);
+            }
+        }
+
+        let statements: Vec<&Statement> = else_body.iter().map(|statement| &**statement).collect();
+        let close = self.block(&statements, close + 2);
+
+        self.line(close)
+    }
+
+    fn function(&mut self, params: &[Param], body: &[Statement], paren: usize) -> usize {
+        self.params(params);
+        self.write(" ");
+
+        let statements: Vec<&Statement> = body.iter().collect();
+
+        self.block(&statements, self.matching(paren) + 1)
+    }
+
+    fn params(&mut self, params: &[Param]) {
+        self.write("(");
+
+        for (index, param) in params.iter().enumerate() {
+            if index > 0 {
+                self.write(", ");
+            }
+
+            match &param.kind {
+                ParamKind::Required(name) => self.write(name),
+                ParamKind::Optional(name, default) => {
+                    self.write(&format!("{} = ", name));
+                    self.expr(default);
+                }
+                ParamKind::Rest(name) => self.write(&format!("...{}", name)),
+            }
+        }
+
+        self.write(")");
+    }
+
+    fn lambda(&mut self, expr: &Expr, params: &[Param], body: &[Statement]) {
+        let paren = self.find(self.token_at(&expr.span), &TokenKind::Def) + 1;
+        let inline = expr.span.line == expr.span.end_line
+            && matches!(
+                body,
+                [statement] if !matches!(
+                    statement.kind,
+                    StatementKind::If(..)
+                        | StatementKind::While(..)
+                        | StatementKind::For(..)
+                        | StatementKind::FuncDef(..)
+                )
+            );
+
+        self.write("def ");
+
+        if inline {
+            self.params(params);
+            self.write(" { ");
+            self.simple_statement(&body[0]);
+            self.write(" }");
+        } else {
+            self.function(params, body, paren);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::String(string) => self.write(&format!("\"{}\"", string)),
+            ExprKind::Int(int) => self.write(&int.to_string()),
+            ExprKind::BigInt(int) => self.write(&int.to_string()),
+            ExprKind::Float(float) => {
+                let index = self.token_at(&expr.span);
+                let text = match self.tokens[index..]
+                    .iter()
+                    .find(|token| matches!(token.kind, TokenKind::Float(_)))
+                {
+                    Some(token) => self.source(&token.span),
+                    None => format!("{:?}", float),
+                };
+
+                self.write(&text);
+            }
+            ExprKind::Boolean(boolean) => self.write(&boolean.to_string()),
+            ExprKind::Var(id, _) => self.write(id),
+            ExprKind::Call(function, args, named_args) => {
+                self.postfix_target(function);
+                self.write("(");
+
+                for (index, arg) in args.iter().enumerate() {
+                    if index > 0 {
+                        self.write(", ");
+                    }
+
+                    self.expr(arg);
+                }
+
+                for (index, (id, arg)) in named_args.iter().enumerate() {
+                    if index > 0 || !args.is_empty() {
+                        self.write(", ");
+                    }
+
+                    self.write(&format!("{} = ", id));
+                    self.expr(arg);
+                }
+
+                self.write(")");
+            }
+            ExprKind::List(list) => {
+                self.write("[");
+
+                for (index, element) in list.iter().enumerate() {
+                    if index > 0 {
+                        self.write(", ");
+                    }
+
+                    self.expr(element);
+                }
+
+                self.write("]");
+            }
+            ExprKind::Dictionnary(dict) => {
+                let mut entries: Vec<(&String, &Box<Expr>)> = dict.iter().collect();
+
+                entries.sort_by_key(|(_, value)| (value.span.line, value.span.column));
+
+                self.write("{");
+
+                for (index, (key, value)) in entries.into_iter().enumerate() {
+                    if index > 0 {
+                        self.write(", ");
+                    }
+
+                    self.write(&format!("\"{}\": ", key));
+                    self.expr(value);
+                }
+
+                self.write("}");
+            }
+            ExprKind::Index(target, index) => {
+                self.postfix_target(target);
+                self.write("[");
+                self.expr(index);
+                self.write("]");
+            }
+            ExprKind::Lambda(params, body) => self.lambda(expr, params, body),
+            ExprKind::BinOp(op, left, right) => {
+                let (precedence, right_associative) = op.precedence();
+
+                self.operand(left, precedence, right_associative, true);
+                self.write(&format!(" {} ", op));
+                self.operand(right, precedence, right_associative, false);
+            }
+            ExprKind::Unary(op, operand) => {
+                let level = Self::unary_level(op);
+
+                self.write(match op {
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Not => "not ",
+                });
+
+                match &operand.kind {
+                    ExprKind::BinOp(op, _, _) if op.precedence().0 < level => {
+                        self.parenthesized(operand)
+                    }
+                    _ => self.expr(operand),
+                }
+            }
+        }
+    }
+
+    fn unary_level(op: &UnaryOp) -> u8 {
+        match op {
+            UnaryOp::Neg => BinOp::Pow.precedence().0,
+            UnaryOp::Not => BinOp::Eq.precedence().0,
+        }
+    }
+
+    fn operand(&mut self, operand: &Expr, precedence: u8, right_associative: bool, left: bool) {
+        let needs_parentheses = match &operand.kind {
+            ExprKind::BinOp(op, _, _) => {
+                let inner = op.precedence().0;
+
+                inner < precedence || (inner == precedence && left == right_associative)
+            }
+            ExprKind::Unary(op, _) => left && precedence >= Self::unary_level(op),
+            _ => false,
+        };
+
+        if needs_parentheses {
+            self.parenthesized(operand);
+        } else {
+            self.expr(operand);
+        }
+    }
+
+    fn postfix_target(&mut self, target: &Expr) {
+        match target.kind {
+            ExprKind::BinOp(..) | ExprKind::Unary(..) => self.parenthesized(target),
+            _ => self.expr(target),
+        }
+    }
+
+    fn parenthesized(&mut self, expr: &Expr) {
+        self.write("(");
+        self.expr(expr);
+        self.write(")");
+    }
+
+    fn source(&self, span: &Span) -> String {
+        self.lines[span.line - 1]
+            .chars()
+            .skip(span.column - 1)
+            .take(span.length)
+            .collect()
+    }
+
+    pub fn format(mut self, statements: &[Statement]) -> String {
+        for statement in statements {
+            self.statement(statement);
+        }
+
+        self.leading_comments(usize::MAX);
+
+        self.output
+    }
+}
+
+pub fn format(content: &str, file_path: PathBuf) -> Result<String> {
+    let mut lexer = Lexer::new(content, file_path.clone()).with_comments();
+    let tokens = lexer.tokenize()?.clone();
+
+    let code: Vec<Token> = tokens
+        .iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Comment(_)))
+        .cloned()
+        .collect();
+    let statements = Parser::new(&code).parse()?;
+
+    let output = Formatter::new(content, tokens).format(&statements);
+
+    let mut lexer = Lexer::new(&output, file_path);
+    let tokens = lexer.tokenize()?;
+
+    if Parser::new(tokens).parse().is_err() {
+        return Err("The formatter produced invalid code, please report this bug!".into());
+    }
+
+    Ok(output)
+}
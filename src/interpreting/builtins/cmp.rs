@@ -1,3 +1,4 @@
+use crate::error::Result;
 use crate::interpreting::interpreter::Interpreter;
 use crate::interpreting::typing::Literal;
 use crate::parsing::typing::Expr;
@@ -5,98 +6,58 @@ use std::cmp::Ordering;
 
 impl PartialOrd for Literal {
     fn partial_cmp(&self, right: &Self) -> Option<Ordering> {
-        Some(match self {
-            Literal::String(left_str) => match right {
-                Literal::String(right_str) => left_str.len().cmp(&right_str.len()),
-                _ => return None,
-            },
-            Literal::Int(left_int) => match right {
-                Literal::Int(right_int) => left_int.cmp(&right_int),
-                Literal::Float(right_float) => {
-                    (*left_int as f64).partial_cmp(&right_float).unwrap()
-                }
-                _ => return None,
-            },
-            Literal::Float(left_float) => match right {
-                Literal::Float(right_float) => left_float.partial_cmp(&right_float).unwrap(),
-                Literal::Int(right_int) => left_float.partial_cmp(&(*right_int as f64)).unwrap(),
-                _ => return None,
-            },
-            _ => return None,
-        })
+        match (self, right) {
+            (Literal::String(left), Literal::String(right)) => Some(left.len().cmp(&right.len())),
+            (Literal::Int(left), Literal::Int(right)) => Some(left.cmp(right)),
+            (Literal::Int(_) | Literal::BigInt(_), Literal::Int(_) | Literal::BigInt(_)) => {
+                Some(self.to_bigint()?.cmp(&right.to_bigint()?))
+            }
+            (left, right) => left.to_float()?.partial_cmp(&right.to_float()?),
+        }
     }
 }
 
 impl PartialEq for Literal {
     fn eq(&self, right: &Self) -> bool {
-        match self {
-            Literal::String(left_str) => match right {
-                Literal::String(right_str) => left_str == right_str,
-                _ => false,
-            },
-            Literal::Int(left_int) => match right {
-                Literal::Int(right_int) => left_int == right_int,
-                Literal::Float(right_float) => (*left_int as f64) == *right_float,
+        match (self, right) {
+            (Literal::String(left), Literal::String(right)) => left == right,
+            (Literal::Boolean(left), Literal::Boolean(right)) => left == right,
+            (Literal::Int(left), Literal::Int(right)) => left == right,
+            (Literal::Int(_) | Literal::BigInt(_), Literal::Int(_) | Literal::BigInt(_)) => {
+                self.to_bigint() == right.to_bigint()
+            }
+            (left, right) => match (left.to_float(), right.to_float()) {
+                (Some(left), Some(right)) => left == right,
                 _ => false,
             },
-            Literal::Float(left_float) => match right {
-                Literal::Float(right_float) => left_float == right_float,
-                Literal::Int(right_int) => *left_float == (*right_int as f64),
-                _ => false,
-            },
-            Literal::Boolean(left_bool) => match right {
-                Literal::Boolean(right_bool) => left_bool == right_bool,
-                _ => false,
-            },
-            _ => false,
         }
     }
-
-    fn ne(&self, right: &Self) -> bool {
-        !(self == right)
-    }
 }
 
-impl<'a> Interpreter<'a> {
-    pub fn lt(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
-
-        Ok(Literal::Boolean(left_literal < right_literal))
-    }
-
-    pub fn gt(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
-
-        Ok(Literal::Boolean(left_literal > right_literal))
+impl Interpreter {
+    // A non-boolean operand is reported at the operand itself
+    fn operand(&mut self, expr: &Expr) -> Result<bool> {
+        self.eval_condition(expr)
+            .map_err(|error| error.or_span(&expr.span))
     }
 
-    pub fn eq(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
-
-        Ok(Literal::Boolean(left_literal == right_literal))
-    }
-
-    pub fn ne(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
+    pub fn and(&mut self, left: &Expr, right: &Expr) -> Result<Literal> {
+        if !self.operand(left)? {
+            return Ok(Literal::Boolean(false));
+        }
 
-        Ok(Literal::Boolean(left_literal != right_literal))
+        Ok(Literal::Boolean(self.operand(right)?))
     }
 
-    pub fn le(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
+    pub fn or(&mut self, left: &Expr, right: &Expr) -> Result<Literal> {
+        if self.operand(left)? {
+            return Ok(Literal::Boolean(true));
+        }
 
-        Ok(Literal::Boolean(left_literal <= right_literal))
+        Ok(Literal::Boolean(self.operand(right)?))
     }
 
-    pub fn ge(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
-
-        Ok(Literal::Boolean(left_literal >= right_literal))
+    pub fn not(&mut self, expr: &Expr) -> Result<Literal> {
+        Ok(Literal::Boolean(!self.eval_condition(expr)?))
     }
 }
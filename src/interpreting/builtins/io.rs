@@ -1,14 +1,16 @@
-use crate::interpreting::interpreter::Interpreter;
+use crate::error::Result;
+use crate::interpreting::builtins::Runtime;
 use crate::interpreting::typing::Literal;
-use crate::parsing::typing::Expr;
 use std::io::Write;
 
-impl<'a> Interpreter<'a> {
-    pub fn put_str(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        for (i, arg) in args.iter().enumerate() {
-            match self.get_literal(*arg.clone())? {
+impl dyn Runtime {
+    pub fn put_str(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        let count = args.len();
+
+        for (i, arg) in args.into_iter().enumerate() {
+            match arg {
                 Literal::String(string) => {
-                    if i == args.len() - 1 {
+                    if i == count - 1 {
                         print!("{}", string);
                     } else {
                         print!("{} ", string);
@@ -18,7 +20,8 @@ impl<'a> Interpreter<'a> {
                     return Err(format!(
                         "The `putStr` function expected a String, not `{}`",
                         unexpected
-                    ))
+                    )
+                    .into())
                 }
             };
         }
@@ -26,7 +29,7 @@ impl<'a> Interpreter<'a> {
         Ok(Literal::None)
     }
 
-    pub fn put_str_ln(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
+    pub fn put_str_ln(&mut self, args: Vec<Literal>) -> Result<Literal> {
         self.put_str(args)?;
 
         println!();
@@ -34,19 +37,19 @@ impl<'a> Interpreter<'a> {
         Ok(Literal::None)
     }
 
-    pub fn print(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
+    pub fn print(&mut self, args: Vec<Literal>) -> Result<Literal> {
         for (i, arg) in args.iter().enumerate() {
             if i == args.len() - 1 {
-                print!("{}", self.get_literal(*arg.clone())?);
+                print!("{}", arg);
             } else {
-                print!("{} ", self.get_literal(*arg.clone())?);
+                print!("{} ", arg);
             }
         }
 
         Ok(Literal::None)
     }
 
-    pub fn print_ln(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
+    pub fn print_ln(&mut self, args: Vec<Literal>) -> Result<Literal> {
         self.print(args)?;
 
         println!();
@@ -54,19 +57,19 @@ impl<'a> Interpreter<'a> {
         Ok(Literal::None)
     }
 
-    pub fn flush_stdout(&mut self, _: Vec<Box<Expr>>) -> Result<Literal, String> {
+    pub fn flush_stdout(&mut self, _: Vec<Literal>) -> Result<Literal> {
         match std::io::stdout().flush() {
             Ok(_) => Ok(Literal::None),
-            Err(e) => Err(format!("Could not flush stdout: {}", e)),
+            Err(e) => Err(format!("Could not flush stdout: {}", e).into()),
         }
     }
 
-    pub fn read_line(&mut self, _: Vec<Box<Expr>>) -> Result<Literal, String> {
+    pub fn read_line(&mut self, _: Vec<Literal>) -> Result<Literal> {
         let mut input = String::new();
 
         match std::io::stdin().read_line(&mut input) {
-            Ok(_) => Ok(Literal::String(input.trim_end().to_string())),
-            Err(e) => return Err(e.to_string()),
+            Ok(_) => Ok(Literal::String(input.trim_end().into())),
+            Err(e) => Err(e.to_string().into()),
         }
     }
 }
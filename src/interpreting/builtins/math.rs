@@ -1,195 +1,304 @@
-use crate::interpreting::interpreter::Interpreter;
+use crate::error::Result;
+use crate::interpreting::builtins::Runtime;
 use crate::interpreting::typing::Literal;
-use crate::parsing::typing::Expr;
+use crate::parsing::typing::BinOp;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::Zero;
+use std::convert::TryFrom;
 
-impl<'a> Interpreter<'a> {
-    pub fn add(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
+// The largest Integer a power may produce, about a million decimal digits
+const MAX_POW_BITS: u64 = 1 << 22;
 
-        Ok(match (left_literal, right_literal) {
-            (Literal::Int(left), Literal::Int(right)) => Literal::Int(left + right),
-            (Literal::Float(left), Literal::Float(right)) => Literal::Float(left + right),
-            (Literal::Int(left), Literal::Float(right)) => Literal::Float((left as f64) + right),
-            (Literal::String(left), Literal::String(right)) => {
-                Literal::String(format!("{}{}", left, right))
-            }
-            (Literal::Float(left), Literal::Int(right)) => Literal::Float(left + (right as f64)),
-            (left, right) => return Err(format!("Cannot add {} and {}", left, right)),
-        })
+impl Literal {
+    pub fn binary(op: &BinOp, left: Literal, right: Literal) -> Result<Literal> {
+        match op {
+            BinOp::Add => Self::binary_add(left, right),
+            BinOp::Sub => Self::binary_sub(left, right),
+            BinOp::Mul => Self::binary_mul(left, right),
+            BinOp::Div => Self::binary_div(left, right, false),
+            BinOp::FloorDiv => Self::binary_div(left, right, true),
+            BinOp::Mod => Self::binary_mod(left, right),
+            BinOp::Pow => Self::binary_pow(left, right),
+            BinOp::Lt => Ok(Literal::Boolean(left < right)),
+            BinOp::Gt => Ok(Literal::Boolean(left > right)),
+            BinOp::Eq => Ok(Literal::Boolean(left == right)),
+            BinOp::Ne => Ok(Literal::Boolean(left != right)),
+            BinOp::Le => Ok(Literal::Boolean(left <= right)),
+            BinOp::Ge => Ok(Literal::Boolean(left >= right)),
+            BinOp::And | BinOp::Or => unreachable!(),
+        }
     }
 
-    pub fn sub(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
+    fn checked(int: Option<i64>, promote: impl FnOnce() -> BigInt) -> Literal {
+        match int {
+            Some(int) => Literal::Int(int),
+            None => Literal::from(promote()),
+        }
+    }
 
-        Ok(match (left_literal, right_literal) {
-            (Literal::Int(left), Literal::Int(right)) => Literal::Int(left - right),
-            (Literal::Float(left), Literal::Float(right)) => Literal::Float(left - right),
-            (Literal::Int(left), Literal::Float(right)) => Literal::Float((left as f64) - right),
-            (Literal::Float(left), Literal::Int(right)) => Literal::Float(left - (right as f64)),
-            (left, right) => return Err(format!("Cannot substract {} and {}", left, right)),
-        })
+    fn is_zero(literal: &Literal) -> bool {
+        match literal {
+            Literal::Int(int) => *int == 0,
+            Literal::BigInt(int) => int.is_zero(),
+            Literal::Float(float) => *float == 0.0,
+            _ => false,
+        }
     }
 
-    pub fn mul(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
+    fn repeat(string: &str, count: i64) -> Result<Literal> {
+        let count = match usize::try_from(count) {
+            Ok(count) => count,
+            Err(_) => return Err(format!("Cannot repeat a String {} times", count).into()),
+        };
 
-        Ok(match (left_literal, right_literal) {
-            (Literal::Int(left), Literal::Int(right)) => Literal::Int(left * right),
-            (Literal::Float(left), Literal::Float(right)) => Literal::Float(left * right),
-            (Literal::Int(left), Literal::Float(right)) => Literal::Float((left as f64) * right),
-            (Literal::Float(left), Literal::Int(right)) => Literal::Float(left * (right as f64)),
-            (Literal::Int(left), Literal::String(right)) => {
-                Literal::String(right.repeat(left as usize))
+        // A String cannot hold more than `isize::MAX` bytes
+        match string.len().checked_mul(count) {
+            Some(length) if length <= isize::MAX as usize => {
+                Ok(Literal::String(string.repeat(count).into()))
             }
-            (Literal::String(left), Literal::Int(right)) => {
-                Literal::String(left.repeat(right as usize))
+            _ => Err(format!("The String is too long to repeat {} times", count).into()),
+        }
+    }
+
+    fn numeric(
+        left: &Literal,
+        right: &Literal,
+        int: fn(BigInt, BigInt) -> BigInt,
+        float: fn(f64, f64) -> f64,
+    ) -> Option<Literal> {
+        match (left, right) {
+            (Literal::Int(_) | Literal::BigInt(_), Literal::Int(_) | Literal::BigInt(_)) => {
+                Some(Literal::from(int(left.to_bigint()?, right.to_bigint()?)))
             }
-            (left, right) => return Err(format!("Cannot multiply {} and {}", left, right)),
-        })
+            _ => Some(Literal::Float(float(left.to_float()?, right.to_float()?))),
+        }
     }
 
-    pub fn div(
-        &mut self,
-        left: Box<Expr>,
-        right: Box<Expr>,
-        floor: bool,
-    ) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
-
-        Ok(match (left_literal, right_literal) {
-            (Literal::Int(left), Literal::Int(right)) => Literal::Int(left / right),
-            (Literal::Float(left), Literal::Float(right)) => Literal::Float(if floor {
-                (left / right).floor()
-            } else {
-                left / right
-            }),
-            (Literal::Int(left), Literal::Float(right)) => Literal::Float(if floor {
-                ((left as f64) / right).floor()
-            } else {
-                (left as f64) / right
-            }),
-            (Literal::Float(left), Literal::Int(right)) => Literal::Float(if floor {
-                (left / (right as f64)).floor()
-            } else {
-                left / (right as f64)
-            }),
-            (left, right) => return Err(format!("Cannot divide {} and {}", left, right)),
+    pub fn binary_add(left: Literal, right: Literal) -> Result<Literal> {
+        Ok(match (left, right) {
+            (Literal::Int(left), Literal::Int(right)) => {
+                Self::checked(left.checked_add(right), || BigInt::from(left) + right)
+            }
+            (Literal::String(left), Literal::String(right)) => {
+                Literal::String(format!("{}{}", left, right).into())
+            }
+            (Literal::List(left), Literal::List(right)) => {
+                let mut list = left.borrow().clone();
+                list.extend(right.borrow().iter().cloned());
+                Literal::list(list)
+            }
+            (left, right) => match Self::numeric(&left, &right, |l, r| l + r, |l, r| l + r) {
+                Some(literal) => literal,
+                None => return Err(format!("Cannot add {} and {}", left, right).into()),
+            },
         })
     }
 
-    pub fn r#mod(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
+    pub fn binary_sub(left: Literal, right: Literal) -> Result<Literal> {
+        Ok(match (left, right) {
+            (Literal::Int(left), Literal::Int(right)) => {
+                Self::checked(left.checked_sub(right), || BigInt::from(left) - right)
+            }
+            (left, right) => match Self::numeric(&left, &right, |l, r| l - r, |l, r| l - r) {
+                Some(literal) => literal,
+                None => return Err(format!("Cannot substract {} and {}", left, right).into()),
+            },
+        })
+    }
 
-        Ok(match (left_literal, right_literal) {
-            (Literal::Int(left), Literal::Int(right)) => Literal::Int(left % right),
-            (Literal::Float(left), Literal::Float(right)) => Literal::Float(left % right),
-            (Literal::Int(left), Literal::Float(right)) => Literal::Float((left as f64) % right),
-            (Literal::Float(left), Literal::Int(right)) => Literal::Float(left % (right as f64)),
-            (left, right) => return Err(format!("Cannot mod {} and {}", left, right)),
+    pub fn binary_mul(left: Literal, right: Literal) -> Result<Literal> {
+        Ok(match (left, right) {
+            (Literal::Int(left), Literal::Int(right)) => {
+                Self::checked(left.checked_mul(right), || BigInt::from(left) * right)
+            }
+            (Literal::Int(left), Literal::String(right)) => Self::repeat(&right, left)?,
+            (Literal::String(left), Literal::Int(right)) => Self::repeat(&left, right)?,
+            (left, right) => match Self::numeric(&left, &right, |l, r| l * r, |l, r| l * r) {
+                Some(literal) => literal,
+                None => return Err(format!("Cannot multiply {} and {}", left, right).into()),
+            },
         })
     }
 
-    pub fn pow(&mut self, left: Box<Expr>, right: Box<Expr>) -> Result<Literal, String> {
-        let left_literal = self.get_literal(*left)?;
-        let right_literal = self.get_literal(*right)?;
+    // Integer division always floors, so `/` and `//` only differ on floats,
+    // and `%` takes the sign of the divisor to keep `a == (a / b) * b + a % b`.
+    pub fn binary_div(left: Literal, right: Literal, floor: bool) -> Result<Literal> {
+        let float: fn(f64, f64) -> f64 = if floor {
+            |l, r| (l / r).floor()
+        } else {
+            |l, r| l / r
+        };
 
-        Ok(match (left_literal, right_literal) {
-            (Literal::Int(left), Literal::Int(right)) => Literal::Int(left.pow(right as u32)),
-            (Literal::Float(left), Literal::Float(right)) => Literal::Float(left.powf(right)),
-            (Literal::Int(left), Literal::Float(right)) => {
-                Literal::Float((left as f64).powf(right))
+        Ok(match (left, right) {
+            (left, right) if left.to_float().is_some() && Self::is_zero(&right) => {
+                return Err("Division by zero".into())
             }
-            (Literal::Float(left), Literal::Int(right)) => Literal::Float(left.powf(right as f64)),
-            _ => return Err("Pow function works only with numbers".to_string()),
+            (Literal::Int(left), Literal::Int(right)) => Self::checked(
+                left.checked_div(right)
+                    .map(|_| Integer::div_floor(&left, &right)),
+                || BigInt::from(left).div_floor(&BigInt::from(right)),
+            ),
+            (left, right) => match Self::numeric(&left, &right, |l, r| l.div_floor(&r), float) {
+                Some(literal) => literal,
+                None => return Err(format!("Cannot divide {} and {}", left, right).into()),
+            },
         })
     }
 
-    pub fn cos(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if args.len() <= 0 {
-            return Err("Cosine function needs one argument".to_string());
+    pub fn binary_mod(left: Literal, right: Literal) -> Result<Literal> {
+        Ok(match (left, right) {
+            (left, right) if left.to_float().is_some() && Self::is_zero(&right) => {
+                return Err("Modulo by zero".into())
+            }
+            (Literal::Int(left), Literal::Int(right)) => Self::checked(
+                left.checked_rem(right)
+                    .map(|_| Integer::mod_floor(&left, &right)),
+                || BigInt::from(left).mod_floor(&BigInt::from(right)),
+            ),
+            (left, right) => match Self::numeric(
+                &left,
+                &right,
+                |l, r| l.mod_floor(&r),
+                |l, r| match l % r {
+                    m if m != 0.0 && (m < 0.0) != (r < 0.0) => m + r,
+                    m => m,
+                },
+            ) {
+                Some(literal) => literal,
+                None => return Err(format!("Cannot mod {} and {}", left, right).into()),
+            },
+        })
+    }
+
+    // A negative exponent gives a Float, like mixing an Int with a Float.
+    pub fn binary_pow(left: Literal, right: Literal) -> Result<Literal> {
+        let (base, exponent) = match (left.to_float(), right.to_float()) {
+            (Some(base), Some(exponent)) => (base, exponent),
+            _ => return Err("Pow function works only with numbers".into()),
+        };
+
+        if Self::is_zero(&left) && exponent < 0.0 {
+            return Err("Division by zero".into());
+        }
+
+        if !matches!(left, Literal::Int(_) | Literal::BigInt(_))
+            || !matches!(right, Literal::Int(_) | Literal::BigInt(_))
+            || exponent < 0.0
+        {
+            return Ok(Literal::Float(base.powf(exponent)));
         }
 
-        let literal = self.get_literal(*args[0].clone())?;
+        // `base ^ exponent` needs about `exponent * base.bits()` bits
+        let bits = match &left {
+            Literal::Int(int) => BigInt::from(*int).bits(),
+            Literal::BigInt(int) => int.bits(),
+            _ => 0,
+        };
+        let exponent = match &right {
+            Literal::Int(int) => u32::try_from(*int)
+                .ok()
+                .filter(|&exponent| bits <= 1 || exponent as u64 * bits <= MAX_POW_BITS),
+            _ => None,
+        };
 
+        Ok(match (left, exponent) {
+            (Literal::Int(left), Some(exponent)) => {
+                Self::checked(left.checked_pow(exponent), || {
+                    BigInt::from(left).pow(exponent)
+                })
+            }
+            (Literal::BigInt(left), Some(exponent)) => Literal::from(left.pow(exponent)),
+            _ => return Err(format!("The exponent {} is too large", right).into()),
+        })
+    }
+
+    pub fn negate(literal: Literal) -> Result<Literal> {
         Ok(match literal {
-            Literal::Int(int) => Literal::Float((int as f64).cos()),
-            Literal::Float(float) => Literal::Float(float.cos()),
-            _ => return Err("Cosine works only with numbers".to_string()),
+            Literal::Int(int) => Self::checked(int.checked_neg(), || -BigInt::from(int)),
+            Literal::BigInt(int) => Literal::from(-int),
+            Literal::Float(float) => Literal::Float(-float),
+            unexpected => return Err(format!("Cannot negate {}", unexpected).into()),
         })
     }
+}
 
-    pub fn sin(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if args.len() <= 0 {
-            return Err("Sine function needs one argument".to_string());
+impl dyn Runtime {
+    pub fn cos(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() {
+            return Err("Cosine function needs one argument".into());
         }
 
-        let literal = self.get_literal(*args[0].clone())?;
+        let literal = args.into_iter().next().unwrap();
 
-        Ok(match literal {
-            Literal::Int(int) => Literal::Float((int as f64).sin()),
-            Literal::Float(float) => Literal::Float(float.sin()),
-            _ => return Err("Sine works only with numbers".to_string()),
-        })
+        match literal.to_float() {
+            Some(float) => Ok(Literal::Float(float.cos())),
+            None => Err("Cosine works only with numbers".into()),
+        }
     }
 
-    pub fn tan(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if args.len() <= 0 {
-            return Err("Tangent function needs one argument".to_string());
+    pub fn sin(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() {
+            return Err("Sine function needs one argument".into());
         }
 
-        let literal = self.get_literal(*args[0].clone())?;
+        let literal = args.into_iter().next().unwrap();
 
-        Ok(match literal {
-            Literal::Int(int) => Literal::Float((int as f64).cos()),
-            Literal::Float(float) => Literal::Float(float.cos()),
-            _ => return Err("Tangent works only with numbers".to_string()),
-        })
+        match literal.to_float() {
+            Some(float) => Ok(Literal::Float(float.sin())),
+            None => Err("Sine works only with numbers".into()),
+        }
     }
 
-    pub fn acos(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if args.len() <= 0 {
-            return Err("ArcCosine function needs one argument".to_string());
+    pub fn tan(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() {
+            return Err("Tangent function needs one argument".into());
         }
 
-        let literal = self.get_literal(*args[0].clone())?;
+        let literal = args.into_iter().next().unwrap();
 
-        Ok(match literal {
-            Literal::Int(int) => Literal::Float((int as f64).acos()),
-            Literal::Float(float) => Literal::Float(float.acos()),
-            _ => return Err("ArcCosine works only with numbers".to_string()),
-        })
+        match literal.to_float() {
+            Some(float) => Ok(Literal::Float(float.tan())),
+            None => Err("Tangent works only with numbers".into()),
+        }
     }
 
-    pub fn asin(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if args.len() <= 0 {
-            return Err("ArcSine function needs one argument".to_string());
+    pub fn acos(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() {
+            return Err("ArcCosine function needs one argument".into());
         }
 
-        let literal = self.get_literal(*args[0].clone())?;
+        let literal = args.into_iter().next().unwrap();
 
-        Ok(match literal {
-            Literal::Int(int) => Literal::Float((int as f64).asin()),
-            Literal::Float(float) => Literal::Float(float.asin()),
-            _ => return Err("ArcSine works only with numbers".to_string()),
-        })
+        match literal.to_float() {
+            Some(float) => Ok(Literal::Float(float.acos())),
+            None => Err("ArcCosine works only with numbers".into()),
+        }
     }
 
-    pub fn atan(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if args.len() <= 0 {
-            return Err("ArcTangent function needs one argument".to_string());
+    pub fn asin(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() {
+            return Err("ArcSine function needs one argument".into());
         }
 
-        let literal = self.get_literal(*args[0].clone())?;
+        let literal = args.into_iter().next().unwrap();
 
-        Ok(match literal {
-            Literal::Int(int) => Literal::Float((int as f64).acos()),
-            Literal::Float(float) => Literal::Float(float.acos()),
-            _ => return Err("ArcTangent works only with numbers".to_string()),
-        })
+        match literal.to_float() {
+            Some(float) => Ok(Literal::Float(float.asin())),
+            None => Err("ArcSine works only with numbers".into()),
+        }
+    }
+
+    pub fn atan(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() {
+            return Err("ArcTangent function needs one argument".into());
+        }
+
+        let literal = args.into_iter().next().unwrap();
+
+        match literal.to_float() {
+            Some(float) => Ok(Literal::Float(float.atan())),
+            None => Err("ArcTangent works only with numbers".into()),
+        }
     }
 }
@@ -1,96 +1,150 @@
-use crate::{
-    interpreting::{interpreter::Interpreter, typing::Literal},
-    parsing::typing::Expr,
-};
+use crate::error::Result;
+use crate::interpreting::{builtins::Runtime, interpreter::Interpreter, typing::Literal};
+use num_bigint::{BigInt, RandBigInt};
 use rand::Rng;
+use std::cmp::Ordering;
 
-impl<'a> Interpreter<'a> {
-    pub fn range(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
+impl dyn Runtime {
+    pub fn range(&mut self, args: Vec<Literal>) -> Result<Literal> {
         if args.len() < 2 {
-            return Err(format!("Expected 2 arguments, given {}", args.len()));
+            return Err(format!("Expected 2 arguments, given {}", args.len()).into());
         }
 
-        match (
-            self.get_literal(*args[0].clone())?,
-            self.get_literal(*args[1].clone())?,
-        ) {
-            (Literal::Int(start), Literal::Int(stop)) => Ok(Literal::List(
-                (start..stop)
-                    .into_iter()
-                    .map(|i| Box::new(Literal::Int(i)))
-                    .collect(),
+        let mut args = args.into_iter();
+
+        match (args.next().unwrap(), args.next().unwrap()) {
+            (Literal::Int(start), Literal::Int(stop)) => Ok(Literal::list(
+                (start..stop).map(|i| Box::new(Literal::Int(i))).collect(),
             )),
-            _ => Err(format!("Range start and stop must be Integers")),
+            (start, stop) => match (start.to_bigint(), stop.to_bigint()) {
+                (Some(mut i), Some(stop)) => {
+                    let mut list = vec![];
+
+                    while i < stop {
+                        list.push(Box::new(Literal::from(i.clone())));
+                        i += 1u32;
+                    }
+
+                    Ok(Literal::list(list))
+                }
+                _ => Err("Range start and stop must be Integers".into()),
+            },
         }
     }
 
-    pub fn random(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
+    pub fn push(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.len() != 2 {
+            return Err(
+                format!("Expected 2 arguments: list and value, given {}", args.len()).into(),
+            );
+        }
+
+        let mut args = args.into_iter();
+
+        match (args.next().unwrap(), args.next().unwrap()) {
+            (Literal::List(list), value) => {
+                list.borrow_mut().push(Box::new(value));
+                Ok(Literal::None)
+            }
+            (unexpected, _) => Err(format!("Expected List, found {}", unexpected).into()),
+        }
+    }
+
+    pub fn random(&mut self, args: Vec<Literal>) -> Result<Literal> {
         if args.len() < 2 {
-            return Err(format!(
-                "Expected 2 arguments: min and max, given {}",
-                args.len()
-            ));
+            return Err(format!("Expected 2 arguments: min and max, given {}", args.len()).into());
         }
 
         let mut rng = rand::thread_rng();
 
-        match (
-            self.get_literal(*args[0].clone())?,
-            self.get_literal(*args[1].clone())?,
-        ) {
-            (Literal::Int(min), Literal::Int(max)) => Ok(Literal::Int(rng.gen_range(min..max))),
-            (Literal::Float(min), Literal::Float(max)) => {
-                Ok(Literal::Float(rng.gen_range(min..max)))
-            }
-            (Literal::Int(min), Literal::Float(max)) => {
-                Ok(Literal::Float(rng.gen_range((min as f64)..max)))
-            }
-            (Literal::Float(min), Literal::Int(max)) => {
-                Ok(Literal::Float(rng.gen_range(min..(max as f64))))
+        let mut args = args.into_iter();
+        let (min, max) = (args.next().unwrap(), args.next().unwrap());
+
+        // `gen_range` panics on an empty range or one wider than any float
+        if let Some(width) = max
+            .to_float()
+            .zip(min.to_float())
+            .map(|(max, min)| max - min)
+        {
+            if min.partial_cmp(&max) != Some(Ordering::Less) || width.is_infinite() {
+                let message = format!("Cannot pick a random number between {} and {}", min, max);
+                return Err(message.into());
             }
-            _ => Err(format!("Random min and max must be Numbers.")),
+        }
+
+        match (min, max) {
+            (Literal::Int(min), Literal::Int(max)) => Ok(Literal::Int(rng.gen_range(min..max))),
+            (min, max) => match (min.to_bigint(), max.to_bigint()) {
+                (Some(min), Some(max)) => Ok(Literal::from(rng.gen_bigint_range(&min, &max))),
+                _ => match (min.to_float(), max.to_float()) {
+                    (Some(min), Some(max)) => Ok(Literal::Float(rng.gen_range(min..max))),
+                    _ => Err("Random min and max must be Numbers.".into()),
+                },
+            },
         }
     }
 
-    pub fn parse_int(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if args.len() < 1 {
-            return Err(format!("Expected 1 argument, given {}", args.len()));
+    pub fn parse_int(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() {
+            return Err(format!("Expected 1 argument, given {}", args.len()).into());
         }
 
-        let raw_int = match self.get_literal(*args[0].clone())? {
+        let raw_int = match args.into_iter().next().unwrap() {
             Literal::String(string) => string,
-            unexpected => return Err(format!("Expected String, found {}", unexpected)),
+            unexpected => return Err(format!("Expected String, found {}", unexpected).into()),
         };
 
-        Ok(match raw_int.parse::<i32>() {
+        Ok(match raw_int.parse::<i64>() {
             Ok(int) => Literal::Int(int),
-            Err(_) => return Err(format!("Invalid Integer {}", raw_int)),
+            Err(_) => match raw_int.parse::<BigInt>() {
+                Ok(int) => Literal::from(int),
+                Err(_) => return Err(format!("Invalid Integer {}", raw_int).into()),
+            },
         })
     }
 
-    pub fn parse_float(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if args.len() < 1 {
-            return Err(format!("Expected 1 argument, given {}", args.len()));
+    pub fn parse_float(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() {
+            return Err(format!("Expected 1 argument, given {}", args.len()).into());
         }
 
-        let raw_float = match self.get_literal(*args[0].clone())? {
+        let raw_float = match args.into_iter().next().unwrap() {
             Literal::String(string) => string,
-            unexpected => return Err(format!("Expected String, found {}", unexpected)),
+            unexpected => return Err(format!("Expected String, found {}", unexpected).into()),
         };
 
         Ok(match raw_float.parse::<f64>() {
             Ok(float) => Literal::Float(float),
-            Err(_) => return Err(format!("Invalid Float {}", raw_float)),
+            Err(_) => return Err(format!("Invalid Float {}", raw_float).into()),
         })
     }
 
-    pub fn to_string(&mut self, args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if args.len() < 1 {
-            return Err(format!("Expected 1 argument, given {}", args.len()));
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_string(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() {
+            return Err(format!("Expected 1 argument, given {}", args.len()).into());
+        }
+
+        Ok(Literal::String(args[0].to_string().into()))
+    }
+
+    pub fn assert(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.is_empty() || args.len() > 2 {
+            return Err(format!("Expected 1 or 2 arguments, given {}", args.len()).into());
+        }
+
+        let mut args = args.into_iter();
+
+        if Interpreter::condition(args.next().unwrap())? {
+            return Ok(Literal::None);
         }
 
-        Ok(Literal::String(
-            self.get_literal(*args[0].clone())?.to_string(),
-        ))
+        match args.next() {
+            Some(message) => match message {
+                Literal::String(message) => Err(format!("Assertion failed: {}", message).into()),
+                message => Err(format!("Assertion failed: {}", message).into()),
+            },
+            None => Err("Assertion failed!".into()),
+        }
     }
 }
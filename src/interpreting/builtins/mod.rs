@@ -1,4 +1,58 @@
+use crate::error::Result;
+use crate::interpreting::gc::Heap;
+use crate::interpreting::typing::Literal;
+
 pub mod cmp;
 pub mod io;
 pub mod math;
 pub mod misc;
+pub mod sys;
+
+pub type Builtin = fn(&mut (dyn Runtime + 'static), Vec<Literal>) -> Result<Literal>;
+
+// What builtins and host functions need from the backend running them
+pub trait Runtime {
+    fn get_args(&self) -> Literal;
+    fn call(&mut self, function: Literal, args: Vec<Literal>) -> Result<Literal>;
+    fn get_global(&self, id: &str) -> Option<Literal>;
+    fn set_global(&mut self, id: String, literal: Literal);
+    fn heap(&mut self) -> &mut Heap;
+    // Runs the cycle collector with everything the backend still uses as roots
+    fn collect(&mut self) -> usize;
+}
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("putStr", <dyn Runtime>::put_str),
+    ("putStrLn", <dyn Runtime>::put_str_ln),
+    ("print", <dyn Runtime>::print),
+    ("printLn", <dyn Runtime>::print_ln),
+    ("flushStdout", <dyn Runtime>::flush_stdout),
+    ("readLine", <dyn Runtime>::read_line),
+    ("cos", <dyn Runtime>::cos),
+    ("sin", <dyn Runtime>::sin),
+    ("tan", <dyn Runtime>::tan),
+    ("acos", <dyn Runtime>::acos),
+    ("asin", <dyn Runtime>::asin),
+    ("atan", <dyn Runtime>::atan),
+    ("range", <dyn Runtime>::range),
+    ("push", <dyn Runtime>::push),
+    ("random", <dyn Runtime>::random),
+    ("parseInt", <dyn Runtime>::parse_int),
+    ("parseFloat", <dyn Runtime>::parse_float),
+    ("toString", <dyn Runtime>::to_string),
+    ("assert", <dyn Runtime>::assert),
+    ("args", <dyn Runtime>::args),
+    ("getEnv", <dyn Runtime>::get_env),
+    ("setEnv", <dyn Runtime>::set_env),
+    ("exit", <dyn Runtime>::exit),
+    ("gcCollect", <dyn Runtime>::gc_collect),
+    ("gcStats", <dyn Runtime>::gc_stats),
+];
+
+pub fn names() -> Vec<&'static str> {
+    BUILTINS.iter().map(|(name, _)| *name).collect()
+}
+
+pub fn index(name: &str) -> Option<usize> {
+    BUILTINS.iter().position(|(builtin, _)| *builtin == name)
+}
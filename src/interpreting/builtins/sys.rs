@@ -0,0 +1,107 @@
+use crate::error::{FlushError, Result};
+use crate::interpreting::builtins::Runtime;
+use crate::interpreting::typing::Literal;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::env;
+
+impl dyn Runtime {
+    pub fn args(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if !args.is_empty() {
+            return Err(format!("Expected 0 arguments, given {}", args.len()).into());
+        }
+
+        Ok(self.get_args())
+    }
+
+    pub fn get_env(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.len() != 1 {
+            return Err(format!("Expected 1 argument, given {}", args.len()).into());
+        }
+
+        let name = match args.into_iter().next().unwrap() {
+            Literal::String(name) => name,
+            unexpected => return Err(format!("Expected String, found {}", unexpected).into()),
+        };
+
+        Ok(match env::var(&*name) {
+            Ok(value) => Literal::String(value.into()),
+            Err(_) => Literal::None,
+        })
+    }
+
+    pub fn set_env(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.len() != 2 {
+            return Err(
+                format!("Expected 2 arguments: name and value, given {}", args.len()).into(),
+            );
+        }
+
+        let mut args = args.into_iter();
+
+        match (args.next().unwrap(), args.next().unwrap()) {
+            (Literal::String(name), Literal::String(value)) => {
+                if name.is_empty()
+                    || name.contains('=')
+                    || name.contains('\0')
+                    || value.contains('\0')
+                {
+                    return Err(format!("Invalid environment variable `{}`", name).into());
+                }
+
+                env::set_var(&*name, &*value);
+
+                Ok(Literal::None)
+            }
+            _ => Err("Environment variable names and values must be Strings".into()),
+        }
+    }
+
+    pub fn exit(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if args.len() > 1 {
+            return Err(format!("Expected 0 or 1 argument, given {}", args.len()).into());
+        }
+
+        let code = match args.into_iter().next() {
+            Some(code) => match code {
+                Literal::Int(code) => match i32::try_from(code) {
+                    Ok(code) => code,
+                    Err(_) => return Err(format!("Invalid exit code {}", code).into()),
+                },
+                unexpected => return Err(format!("Expected Integer, found {}", unexpected).into()),
+            },
+            None => 0,
+        };
+
+        Err(FlushError::exit(code))
+    }
+
+    pub fn gc_collect(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if !args.is_empty() {
+            return Err(format!("Expected 0 arguments, given {}", args.len()).into());
+        }
+
+        Ok(Literal::Int(self.collect() as i64))
+    }
+
+    pub fn gc_stats(&mut self, args: Vec<Literal>) -> Result<Literal> {
+        if !args.is_empty() {
+            return Err(format!("Expected 0 arguments, given {}", args.len()).into());
+        }
+
+        let stats = self.heap().stats();
+        let mut dict = HashMap::new();
+
+        for (key, value) in [
+            ("tracked", stats.tracked),
+            ("allocated", stats.allocated),
+            ("threshold", stats.threshold),
+            ("collections", stats.collections),
+            ("freed", stats.freed),
+        ] {
+            dict.insert(key.to_string(), Box::new(Literal::Int(value as i64)));
+        }
+
+        Ok(Literal::dictionnary(dict))
+    }
+}
@@ -0,0 +1,46 @@
+use crate::interpreting::typing::Literal;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::convert::TryFrom;
+
+impl Literal {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Literal::String(_) => "String",
+            Literal::Int(_) | Literal::BigInt(_) => "Int",
+            Literal::Float(_) => "Float",
+            Literal::Boolean(_) => "Boolean",
+            Literal::List(_) => "List",
+            Literal::Dictionnary(_) => "Dictionnary",
+            Literal::Function(_) | Literal::Closure(_) | Literal::Host(_) => "Function",
+            Literal::None => "None",
+        }
+    }
+
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Literal::Int(int) => Some(BigInt::from(*int)),
+            Literal::BigInt(int) => Some(int.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn to_float(&self) -> Option<f64> {
+        match self {
+            Literal::Int(int) => Some(*int as f64),
+            Literal::BigInt(int) => int.to_f64(),
+            Literal::Float(float) => Some(*float),
+            _ => None,
+        }
+    }
+}
+
+impl From<BigInt> for Literal {
+    fn from(int: BigInt) -> Self {
+        // Only integers that do not fit in 64 bits are kept as a `BigInt`
+        match i64::try_from(&int) {
+            Ok(int) => Literal::Int(int),
+            Err(_) => Literal::BigInt(int),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use crate::compiling::typing::Closure;
+use crate::interpreting::typing::{Function, Literal, Variable};
+use crate::value::Value;
+use std::cell::RefCell;
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
+
+const INITIAL_THRESHOLD: usize = 10_000;
+const INITIAL_PINS: usize = 64;
+
+#[derive(Clone)]
+pub enum Object {
+    List(Weak<RefCell<Vec<Box<Literal>>>>),
+    Dictionnary(Weak<RefCell<HashMap<String, Box<Literal>>>>),
+    Slots(Weak<RefCell<Vec<Option<Variable>>>>),
+    Scope(Weak<RefCell<HashMap<String, Variable>>>),
+    Function(Weak<Function>),
+    Closure(Weak<Closure>),
+    Cell(Weak<RefCell<Option<Literal>>>),
+}
+
+pub struct Stats {
+    pub tracked: usize,
+    pub allocated: usize,
+    pub threshold: usize,
+    pub collections: usize,
+    pub freed: usize,
+}
+
+// Every interpreter and virtual machine owns a heap, which knows the objects
+// that can be part of a cycle. Those are only cleared by `collect`, which is
+// given the roots by its owner: the heap never guesses what is still in use.
+pub struct Heap {
+    objects: HashMap<usize, Object>,
+    pins: Vec<Weak<Literal>>,
+    pins_threshold: usize,
+    allocated: usize,
+    threshold: usize,
+    collections: usize,
+    freed: usize,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self {
+            objects: HashMap::new(),
+            pins: vec![],
+            pins_threshold: INITIAL_PINS,
+            allocated: 0,
+            threshold: INITIAL_THRESHOLD,
+            collections: 0,
+            freed: 0,
+        }
+    }
+}
+
+impl Object {
+    pub fn of(literal: &Literal) -> Option<Object> {
+        match literal {
+            Literal::List(list) => Some(Object::List(Rc::downgrade(list))),
+            Literal::Dictionnary(dict) => Some(Object::Dictionnary(Rc::downgrade(dict))),
+            Literal::Function(function) => Some(Object::Function(Rc::downgrade(function))),
+            Literal::Closure(closure) => Some(Object::Closure(Rc::downgrade(closure))),
+            _ => None,
+        }
+    }
+
+    pub fn variables<'a>(variables: impl IntoIterator<Item = &'a Variable>) -> Vec<Object> {
+        variables
+            .into_iter()
+            .filter_map(|variable| Object::of(&variable.literal))
+            .collect()
+    }
+
+    fn address(&self) -> usize {
+        match self {
+            Object::List(list) => Weak::as_ptr(list) as *const () as usize,
+            Object::Dictionnary(dict) => Weak::as_ptr(dict) as *const () as usize,
+            Object::Slots(slots) => Weak::as_ptr(slots) as *const () as usize,
+            Object::Scope(scope) => Weak::as_ptr(scope) as *const () as usize,
+            Object::Function(function) => Weak::as_ptr(function) as *const () as usize,
+            Object::Closure(closure) => Weak::as_ptr(closure) as *const () as usize,
+            Object::Cell(cell) => Weak::as_ptr(cell) as *const () as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Object::List(list) => list.strong_count(),
+            Object::Dictionnary(dict) => dict.strong_count(),
+            Object::Slots(slots) => slots.strong_count(),
+            Object::Scope(scope) => scope.strong_count(),
+            Object::Function(function) => function.strong_count(),
+            Object::Closure(closure) => closure.strong_count(),
+            Object::Cell(cell) => cell.strong_count(),
+        }
+    }
+
+    // Collections only happen between statements or instructions, when no
+    // container is borrowed, so every edge is seen
+    fn edges(&self, edges: &mut Vec<Object>) {
+        match self {
+            Object::List(list) => {
+                if let Some(Ok(list)) = list.upgrade().as_ref().map(|list| list.try_borrow()) {
+                    edges.extend(list.iter().filter_map(|literal| Object::of(literal)));
+                }
+            }
+            Object::Dictionnary(dict) => {
+                if let Some(Ok(dict)) = dict.upgrade().as_ref().map(|dict| dict.try_borrow()) {
+                    edges.extend(dict.values().filter_map(|literal| Object::of(literal)));
+                }
+            }
+            Object::Slots(slots) => {
+                if let Some(Ok(slots)) = slots.upgrade().as_ref().map(|slots| slots.try_borrow()) {
+                    edges.extend(Object::variables(slots.iter().flatten()));
+                }
+            }
+            Object::Scope(scope) => {
+                if let Some(Ok(scope)) = scope.upgrade().as_ref().map(|scope| scope.try_borrow()) {
+                    edges.extend(Object::variables(scope.values()));
+                }
+            }
+            Object::Function(function) => {
+                if let Some(function) = function.upgrade() {
+                    edges.extend(
+                        function
+                            .scopes
+                            .iter()
+                            .map(|scope| Object::Slots(Rc::downgrade(scope))),
+                    );
+                    edges.push(Object::Scope(Rc::downgrade(&function.globals)));
+                }
+            }
+            Object::Closure(closure) => {
+                if let Some(closure) = closure.upgrade() {
+                    edges.extend(
+                        closure
+                            .upvalues
+                            .iter()
+                            .map(|cell| Object::Cell(Rc::downgrade(cell))),
+                    );
+                    edges.push(Object::Scope(Rc::downgrade(&closure.globals)));
+                }
+            }
+            Object::Cell(cell) => {
+                if let Some(Ok(cell)) = cell.upgrade().as_ref().map(|cell| cell.try_borrow()) {
+                    edges.extend(cell.as_ref().and_then(Object::of));
+                }
+            }
+        }
+    }
+
+    // Every cycle goes through a mutable container, so emptying the
+    // unreachable ones is enough for reference counting to free the rest.
+    fn clear(&self) {
+        match self {
+            Object::List(list) => {
+                if let Some(Ok(mut list)) =
+                    list.upgrade().as_ref().map(|list| list.try_borrow_mut())
+                {
+                    drop(std::mem::take(&mut *list));
+                }
+            }
+            Object::Dictionnary(dict) => {
+                if let Some(Ok(mut dict)) =
+                    dict.upgrade().as_ref().map(|dict| dict.try_borrow_mut())
+                {
+                    drop(std::mem::take(&mut *dict));
+                }
+            }
+            Object::Slots(slots) => {
+                if let Some(Ok(mut slots)) =
+                    slots.upgrade().as_ref().map(|slots| slots.try_borrow_mut())
+                {
+                    drop(std::mem::take(&mut *slots));
+                }
+            }
+            Object::Scope(scope) => {
+                if let Some(Ok(mut scope)) =
+                    scope.upgrade().as_ref().map(|scope| scope.try_borrow_mut())
+                {
+                    drop(std::mem::take(&mut *scope));
+                }
+            }
+            Object::Cell(cell) => {
+                if let Some(Ok(mut cell)) =
+                    cell.upgrade().as_ref().map(|cell| cell.try_borrow_mut())
+                {
+                    drop(cell.take());
+                }
+            }
+            Object::Function(_) | Object::Closure(_) => {}
+        }
+    }
+}
+
+impl Heap {
+    pub fn track(&mut self, object: Object) {
+        if self.objects.insert(object.address(), object).is_none() {
+            self.allocated += 1;
+        }
+    }
+
+    // Tracks a value created outside of the interpreter, by a builtin, the
+    // host or another module, with everything it references
+    pub fn adopt(&mut self, literal: &Literal) {
+        let mut pending: Vec<Object> = Object::of(literal).into_iter().collect();
+
+        while let Some(object) = pending.pop() {
+            let address = object.address();
+
+            if !self.objects.contains_key(&address) {
+                object.edges(&mut pending);
+                self.track(object);
+            }
+        }
+    }
+
+    // The host can keep a `Value` after the interpreter is done with it, so
+    // it stays a root for as long as one of its clones is alive
+    pub fn pin(&mut self, value: &Value) {
+        if Object::of(&value.0).is_none() {
+            return;
+        }
+
+        if self.pins.len() >= self.pins_threshold {
+            self.pins.retain(|pin| pin.strong_count() > 0);
+            self.pins_threshold = max(INITIAL_PINS, self.pins.len() * 2);
+        }
+
+        self.pins.push(Rc::downgrade(&value.0));
+    }
+
+    pub fn export(&mut self, literal: Literal) -> Value {
+        let value = Value::new(literal);
+        self.pin(&value);
+
+        value
+    }
+
+    pub fn import(&mut self, value: &Value) -> Literal {
+        self.pin(value);
+        self.adopt(&value.0);
+
+        value.literal()
+    }
+
+    pub fn due(&self) -> bool {
+        self.allocated >= self.threshold
+    }
+
+    pub fn collect(&mut self, mut roots: Vec<Object>) -> usize {
+        self.objects.retain(|_, object| object.strong_count() > 0);
+        self.pins.retain(|pin| pin.strong_count() > 0);
+
+        for pin in &self.pins {
+            if let Some(literal) = pin.upgrade() {
+                roots.extend(Object::of(&literal));
+            }
+        }
+
+        let mut marked: HashSet<usize> = HashSet::new();
+
+        while let Some(object) = roots.pop() {
+            if marked.insert(object.address()) {
+                object.edges(&mut roots);
+            }
+        }
+
+        let garbage: Vec<Object> = self
+            .objects
+            .iter()
+            .filter(|(address, _)| !marked.contains(address))
+            .map(|(_, object)| object.clone())
+            .collect();
+
+        self.collections += 1;
+        self.freed += garbage.len();
+        self.allocated = 0;
+        self.threshold = max(INITIAL_THRESHOLD, (self.objects.len() - garbage.len()) * 2);
+
+        for object in &garbage {
+            object.clear();
+        }
+
+        garbage.len()
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            tracked: self
+                .objects
+                .values()
+                .filter(|object| object.strong_count() > 0)
+                .count(),
+            allocated: self.allocated,
+            threshold: self.threshold,
+            collections: self.collections,
+            freed: self.freed,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::error::{FlushError, Result};
+use crate::interpreting::builtins::Runtime;
+use crate::interpreting::typing::Literal;
+use crate::value::Value;
+use std::convert::TryFrom;
+use std::fmt;
+
+pub type HostFn = dyn Fn(&mut Ctx, &[Value]) -> Result<Value>;
+
+pub struct HostFunction {
+    pub name: String,
+    pub function: Box<HostFn>,
+}
+
+impl HostFunction {
+    pub fn call(&self, runtime: &mut dyn Runtime, args: Vec<Literal>) -> Result<Literal> {
+        let args: Vec<Value> = args
+            .into_iter()
+            .map(|literal| runtime.heap().export(literal))
+            .collect();
+        let value = (self.function)(
+            &mut Ctx {
+                runtime: &mut *runtime,
+            },
+            &args,
+        )?;
+
+        Ok(runtime.heap().import(&value))
+    }
+}
+
+impl fmt::Debug for HostFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HostFunction({})", self.name)
+    }
+}
+
+pub struct Ctx<'a> {
+    runtime: &'a mut dyn Runtime,
+}
+
+impl<'a> Ctx<'a> {
+    pub fn call(&mut self, function: Value, args: Vec<Value>) -> Result<Value> {
+        let heap = self.runtime.heap();
+        let function = heap.import(&function);
+        let args = args.iter().map(|value| heap.import(value)).collect();
+        let literal = self.runtime.call(function, args)?;
+
+        Ok(self.runtime.heap().export(literal))
+    }
+
+    pub fn get(&mut self, name: &str) -> Option<Value> {
+        let literal = self.runtime.get_global(name)?;
+
+        Some(self.runtime.heap().export(literal))
+    }
+
+    pub fn set(&mut self, name: &str, value: Value) {
+        let literal = self.runtime.heap().import(&value);
+
+        self.runtime.set_global(name.to_string(), literal);
+    }
+}
+
+pub fn check_arity(args: &[Value], min: usize, max: usize) -> Result<()> {
+    if (min..=max).contains(&args.len()) {
+        return Ok(());
+    }
+
+    let expected = match (min, max) {
+        (1, 1) => "1 argument".to_string(),
+        (min, max) if min == max => format!("{} arguments", min),
+        (min, usize::MAX) => format!("at least {} arguments", min),
+        (min, max) => format!("{} to {} arguments", min, max),
+    };
+
+    Err(format!("Expected {}, given {}", expected, args.len()).into())
+}
+
+pub fn get_arg<T: TryFrom<Value, Error = FlushError>>(args: &[Value], index: usize) -> Result<T> {
+    let value = args.get(index).cloned().unwrap_or_default();
+
+    T::try_from(value).map_err(|error| {
+        FlushError::from(format!("{} for the argument {}", error.message, index + 1))
+    })
+}
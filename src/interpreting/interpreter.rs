@@ -1,469 +1,844 @@
-use crate::flush::run;
+use crate::error::{similar_name, FlushError, Frame, Result, Span};
+use crate::flush::{run, Backend};
+use crate::interpreting::builtins::{self, Runtime, BUILTINS};
+use crate::interpreting::gc::{Heap, Object};
+use crate::interpreting::modules::Modules;
+use crate::interpreting::resolver::Resolver;
 use crate::interpreting::typing::*;
 use crate::parsing::typing::*;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::rc::Rc;
 
-pub struct Interpreter<'a> {
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+pub struct Interpreter {
     statements: Vec<Statement>,
     file_path: PathBuf,
-    cache: &'a mut Vec<PathBuf>,
+    modules: Modules,
     eval_main: bool,
-    stack: Vec<HashMap<String, Literal>>,
-    builtins: HashMap<String, fn(&mut Self, Vec<Box<Expr>>) -> Result<Literal, String>>,
+    globals: Scope,
+    stack: Vec<Slots>,
+    // The scopes of the functions that are waiting for a call to return
+    callers: Vec<(Vec<Slots>, Scope)>,
+    // Lists, dictionaries and functions computed by the statements being
+    // evaluated, which may only be held by Rust variables until they end
+    temporaries: Vec<Literal>,
     contexts: Vec<Context>,
-    loops_conditions: Vec<Expr>,
-    position: usize,
+    frames: Vec<Frame>,
+    args: Vec<String>,
+    heap: Heap,
 }
 
-impl<'a> Interpreter<'a> {
+impl Interpreter {
     pub fn new(
-        statements: Vec<Statement>,
+        mut statements: Vec<Statement>,
         file_path: PathBuf,
-        cache: &'a mut Vec<PathBuf>,
+        modules: Modules,
         eval_main: bool,
     ) -> Self {
-        let mut interpreter = Self {
+        Resolver::resolve(&mut statements);
+
+        let globals = Rc::new(RefCell::new(HashMap::new()));
+        let mut heap = Heap::default();
+        heap.track(Object::Scope(Rc::downgrade(&globals)));
+
+        Self {
             statements,
             file_path,
-            cache,
+            modules,
             eval_main,
-            stack: vec![HashMap::new()],
-            builtins: HashMap::new(),
+            globals,
+            stack: vec![],
+            callers: vec![],
+            temporaries: vec![],
             contexts: vec![Context::TopLevel],
-            loops_conditions: vec![],
-            position: 0,
-        };
+            frames: vec![],
+            args: vec![],
+            heap,
+        }
+    }
 
-        interpreter.register_builtins(vec![
-            ("putStr", Self::put_str),
-            ("putStrLn", Self::put_str_ln),
-            ("print", Self::print),
-            ("printLn", Self::print_ln),
-            ("flushStdout", Self::flush_stdout),
-            ("readLine", Self::read_line),
-            ("cos", Self::cos),
-            ("sin", Self::sin),
-            ("tan", Self::tan),
-            ("acos", Self::acos),
-            ("asin", Self::asin),
-            ("atan", Self::atan),
-            ("range", Self::range),
-            ("random", Self::random),
-            ("parseInt", Self::parse_int),
-            ("parseFloat", Self::parse_float),
-            ("toString", Self::to_string),
-        ]);
+    pub fn repl(file_path: PathBuf, modules: Modules) -> Self {
+        modules.borrow_mut().start(file_path.clone());
+
+        let mut interpreter = Self::new(vec![], file_path, modules, false);
+        interpreter.contexts = vec![Context::Repl];
 
         interpreter
     }
 
-    pub fn get_stack(&self) -> HashMap<String, Literal> {
-        self.stack[0].clone()
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
     }
 
-    fn register_builtins(
-        &mut self,
-        builtins: Vec<(
-            impl ToString,
-            fn(&mut Self, Vec<Box<Expr>>) -> Result<Literal, String>,
-        )>,
-    ) {
-        for builtin in builtins {
-            self.builtins.insert(builtin.0.to_string(), builtin.1);
-        }
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
     }
 
-    fn previous(&self) -> &Statement {
-        &self.statements[self.position - 1]
+    pub fn get_stack(&self) -> HashMap<String, Variable> {
+        self.globals.borrow().clone()
     }
 
-    fn advance(&mut self) -> Option<Statement> {
-        if self.is_at_end() {
-            return None;
-        }
+    fn frame(&self, depth: usize) -> &Slots {
+        &self.stack[self.stack.len() - 1 - depth]
+    }
+
+    fn define(&mut self, id: &str, resolution: &Resolution, literal: Literal, span: Span) {
+        let variable = Variable { literal, span };
 
-        self.position += 1;
-        Some(self.previous().clone())
+        match resolution {
+            Resolution::Global => {
+                self.globals.borrow_mut().insert(id.to_string(), variable);
+            }
+            Resolution::Local(depth, slot) => {
+                let mut slots = self.frame(*depth).borrow_mut();
+
+                if *slot >= slots.len() {
+                    slots.resize_with(slot + 1, || None);
+                }
+
+                slots[*slot] = Some(variable);
+            }
+        }
     }
 
-    fn is_at_end(&self) -> bool {
-        self.statements.len() > 0 && self.position == self.statements.len()
+    fn push_scope(&mut self) {
+        self.stack.push(Rc::new(RefCell::new(vec![])));
     }
 
-    fn push(&mut self, id: String, literal: Literal) {
-        let idx = self.stack.len() - 1;
-        self.stack[idx].insert(id, literal);
+    pub fn eval_statement(&mut self, statement: &Statement) -> Result<ControlFlow> {
+        if self.heap.due() {
+            self.collect_garbage();
+        }
+
+        let temporaries = self.temporaries.len();
+        let result = self
+            .eval_statement_kind(statement)
+            .map_err(|error| error.or_span(&statement.span));
+
+        self.temporaries.truncate(temporaries);
+
+        result
     }
 
-    pub fn eval_statement(&mut self, statement: Statement) -> Result<Option<Literal>, String> {
-        Ok(match statement {
-            Statement::VarDef(id, expr) => self.eval_var_def(id, expr)?,
-            Statement::FuncDef(id, args, statements) => self.eval_func_def(id, args, statements)?,
-            Statement::VarSet(id, expr) => self.eval_var_set(id, expr)?,
-            Statement::IndexSet(expr, index, value) => self.eval_index_set(expr, index, value)?,
-            Statement::Return(expr) => Some(self.get_literal(expr)?),
-            Statement::While(condition, statements) => self.eval_while(condition, statements)?,
-            Statement::For(id, list, statements) => self.eval_for(id, list, statements)?,
-            Statement::Break => self.eval_break()?,
-            Statement::Load(file_path) => self.eval_load(file_path)?,
-            Statement::If(condition, if_body, else_body) => {
+    fn eval_statement_kind(&mut self, statement: &Statement) -> Result<ControlFlow> {
+        let span = &statement.span;
+
+        Ok(match &statement.kind {
+            StatementKind::VarDef(id, expr, resolution) => {
+                self.eval_var_def(id, expr, resolution, span)?
+            }
+            StatementKind::FuncDef(id, args, statements, resolution) => {
+                self.eval_func_def(id, args, statements, resolution, span)?
+            }
+            StatementKind::VarSet(id, expr, resolution) => {
+                self.eval_var_set(id, expr, resolution)?
+            }
+            StatementKind::IndexSet(expr, index, value) => {
+                self.eval_index_set(expr, index, value)?
+            }
+            StatementKind::Return(expr) => ControlFlow::Return(self.get_literal(expr)?),
+            StatementKind::While(condition, statements) => {
+                self.eval_while(condition, statements)?
+            }
+            StatementKind::For(id, id_span, list, statements, resolution) => {
+                self.eval_for(id, id_span, list, statements, resolution)?
+            }
+            StatementKind::Break => self.eval_loop_jump("break", ControlFlow::Break)?,
+            StatementKind::Continue => self.eval_loop_jump("continue", ControlFlow::Continue)?,
+            StatementKind::Load(file_path) => self.eval_load(file_path, span)?,
+            StatementKind::If(condition, if_body, else_body) => {
                 self.eval_control_flow(condition, if_body, else_body)?
             }
-            Statement::Expr(expr) => {
-                if !self.contexts.contains(&Context::Function) {
-                    return Err("Cannot evaluate expression outside a function!".to_string());
+            StatementKind::Expr(expr) => {
+                if !self
+                    .contexts
+                    .iter()
+                    .any(|context| matches!(context, Context::Function | Context::Repl))
+                {
+                    return Err(
+                        FlushError::from("Cannot evaluate expression outside a function!")
+                            .with_help("move it into the body of `def main() { ... }`"),
+                    );
                 }
 
                 self.get_literal(expr)?;
-                None
+                ControlFlow::Normal
             }
         })
     }
 
-    fn eval_var_def(&mut self, id: String, expr: Expr) -> Result<Option<Literal>, String> {
-        if self.stack.last().unwrap().contains_key(&id) {
-            return Err(format!("The `{}` variable already exists!", id));
+    fn eval_block(&mut self, statements: &[Box<Statement>]) -> Result<ControlFlow> {
+        for statement in statements {
+            match self.eval_statement(statement)? {
+                ControlFlow::Normal => {}
+                flow => return Ok(flow),
+            }
+        }
+
+        Ok(ControlFlow::Normal)
+    }
+
+    fn eval_scoped_block(&mut self, statements: &[Box<Statement>]) -> Result<ControlFlow> {
+        self.push_scope();
+
+        let result = self.eval_block(statements);
+
+        self.stack.pop();
+
+        result
+    }
+
+    fn eval_var_def(
+        &mut self,
+        id: &str,
+        expr: &Expr,
+        resolution: &Resolution,
+        span: &Span,
+    ) -> Result<ControlFlow> {
+        let defined = match resolution {
+            Resolution::Global => self
+                .globals
+                .borrow()
+                .get(id)
+                .map(|variable| variable.span.clone()),
+            Resolution::Local(depth, slot) => match self.frame(*depth).borrow().get(*slot) {
+                Some(Some(variable)) => Some(variable.span.clone()),
+                _ => None,
+            },
+        };
+
+        if let Some(defined) = defined {
+            return Err(Self::already_exists(id, &defined));
         }
 
         let literal = self.get_literal(expr)?;
-        self.push(id, literal);
+        self.define(id, resolution, literal, span.clone());
 
-        Ok(None)
+        Ok(ControlFlow::Normal)
     }
 
     fn eval_func_def(
         &mut self,
-        id: String,
-        args: Vec<String>,
-        statements: Vec<Statement>,
-    ) -> Result<Option<Literal>, String> {
-        self.push(
-            id.clone(),
-            Literal::Function(id.clone(), args, statements.clone()),
+        id: &str,
+        params: &[Param],
+        statements: &Rc<[Statement]>,
+        resolution: &Resolution,
+        span: &Span,
+    ) -> Result<ControlFlow> {
+        let function = self.function(id, params, statements);
+
+        self.define(
+            id,
+            resolution,
+            Literal::Function(function.clone()),
+            span.clone(),
         );
 
-        if self.eval_main && id.clone() == "main".to_string() {
-            self.eval_call(id.clone(), vec![])?;
+        if self.eval_main && id == "main" {
+            let args = match function.params.first().map(|param| &param.kind) {
+                Some(ParamKind::Rest(_)) => self
+                    .args
+                    .iter()
+                    .map(|arg| Literal::String(arg.as_str().into()))
+                    .collect(),
+                Some(_) => vec![self.get_args()],
+                None => vec![],
+            };
+
+            for arg in &args {
+                self.heap.adopt(arg);
+            }
+
+            self.call_function(Literal::Function(function), args, vec![], span.clone())?;
         }
 
-        Ok(None)
+        Ok(ControlFlow::Normal)
     }
 
-    fn eval_var_set(&mut self, id: String, expr: Expr) -> Result<Option<Literal>, String> {
-        let literal = self.get_literal(expr)?;
+    fn function(
+        &mut self,
+        id: &str,
+        params: &[Param],
+        statements: &Rc<[Statement]>,
+    ) -> Rc<Function> {
+        for scope in &self.stack {
+            self.heap.track(Object::Slots(Rc::downgrade(scope)));
+        }
 
-        self.set_var(id, literal)?;
+        let function = Rc::new(Function {
+            name: id.to_string(),
+            params: params.to_vec(),
+            body: statements.clone(),
+            scopes: self.stack.clone(),
+            globals: self.globals.clone(),
+        });
+        self.heap.track(Object::Function(Rc::downgrade(&function)));
 
-        Ok(None)
+        function
     }
 
-    fn eval_index_set(
+    fn eval_var_set(
         &mut self,
-        expr: Expr,
-        index: Expr,
-        value: Expr,
-    ) -> Result<Option<Literal>, String> {
+        id: &str,
+        expr: &Expr,
+        resolution: &Resolution,
+    ) -> Result<ControlFlow> {
+        let literal = self.get_literal(expr)?;
+
+        self.set_var(id, resolution, literal)?;
+
+        Ok(ControlFlow::Normal)
+    }
+
+    fn eval_index_set(&mut self, expr: &Expr, index: &Expr, value: &Expr) -> Result<ControlFlow> {
         let value = Box::from(self.get_literal(value)?);
+        let literal = self.get_literal(expr)?;
+        let index = self.get_literal(index)?;
 
-        if let Expr::Var(var) = expr.clone() {
-            match self.get_literal(expr.clone())? {
-                Literal::List(mut list) => {
-                    let index = self.get_index(&list, Box::from(index))?;
-                    list[index] = value;
+        Self::set_index(literal, index, value)?;
 
-                    self.set_var(var, Literal::List(list))?;
-                }
-                Literal::Dictionnary(mut dict) => {
-                    let key = match self.get_literal(index.clone())? {
-                        Literal::String(key) => key,
-                        unexpected => {
-                            return Err(format!("Expected `String` found `{:?}`", unexpected))
-                        }
-                    };
+        Ok(ControlFlow::Normal)
+    }
 
-                    dict.insert(key, value);
+    pub fn set_index(literal: Literal, index: Literal, value: Box<Literal>) -> Result<()> {
+        match literal {
+            Literal::List(list) => {
+                let mut list = list.borrow_mut();
+                let index = Self::get_index(&list, index)?;
+                list[index] = value;
 
-                    self.set_var(var, Literal::Dictionnary(dict))?;
-                }
-                _ => unreachable!(),
+                Ok(())
             }
-        }
+            Literal::Dictionnary(dict) => {
+                let key = match index {
+                    Literal::String(key) => key,
+                    unexpected => {
+                        return Err(format!("Expected `String` found `{}`", unexpected).into())
+                    }
+                };
+
+                dict.borrow_mut().insert(key.to_string(), value);
 
-        Ok(None)
+                Ok(())
+            }
+            unexpected => Err(format!("Could not index {}", unexpected).into()),
+        }
     }
 
     fn eval_while(
         &mut self,
-        condition: Expr,
-        statements: Vec<Box<Statement>>,
-    ) -> Result<Option<Literal>, String> {
-        if !self.eval_condition(condition.clone())? {
-            return Ok(None);
-        }
+        condition: &Expr,
+        statements: &[Box<Statement>],
+    ) -> Result<ControlFlow> {
+        self.contexts.push(Context::Loop);
 
-        let mut result: Option<Literal> = None;
+        let result = self.eval_while_loop(condition, statements);
 
-        self.stack.push(HashMap::new());
-        self.contexts.push(Context::Loop);
-        self.loops_conditions.push(condition.clone());
+        self.contexts.pop();
 
-        'main_while: loop {
-            for statement in statements.clone() {
-                if !self.eval_condition(self.loops_conditions.last().unwrap().clone())? {
-                    break 'main_while;
-                }
+        result
+    }
 
-                if let Statement::Return(expr) = *statement {
-                    result = Some(self.get_literal(expr)?);
-                    break 'main_while;
-                }
+    fn eval_while_loop(
+        &mut self,
+        condition: &Expr,
+        statements: &[Box<Statement>],
+    ) -> Result<ControlFlow> {
+        let temporaries = self.temporaries.len();
+
+        while self.eval_condition(condition)? {
+            let flow = self.eval_scoped_block(statements)?;
 
-                self.eval_statement(*statement)?;
+            self.temporaries.truncate(temporaries);
+
+            match flow {
+                ControlFlow::Normal | ControlFlow::Continue => {}
+                ControlFlow::Break => break,
+                flow => return Ok(flow),
             }
         }
 
-        self.stack.pop();
-        self.contexts.pop();
-        self.loops_conditions.pop();
-
-        Ok(result)
+        Ok(ControlFlow::Normal)
     }
 
     fn eval_for(
         &mut self,
-        id: String,
-        expr: Expr,
-        statements: Vec<Box<Statement>>,
-    ) -> Result<Option<Literal>, String> {
-        match self.get_var(id.clone()) {
-            Ok(_) => return Err(format!("The `{}` variable already exists!", id)),
-            _ => (),
-        }
-
-        if let Literal::List(list) = self.get_literal(expr)? {
-            self.stack.push(HashMap::new());
-            self.contexts.push(Context::Loop);
-            self.loops_conditions.push(Expr::Boolean(true));
-
-            for element in list {
-                if !self.eval_condition(self.loops_conditions.last().unwrap().clone())? {
-                    break;
-                }
+        id: &str,
+        id_span: &Span,
+        expr: &Expr,
+        statements: &[Box<Statement>],
+        resolution: &Resolution,
+    ) -> Result<ControlFlow> {
+        if let Some(defined) = self.with_var(id, resolution, |variable| variable.span.clone()) {
+            return Err(Self::already_exists(id, &defined));
+        }
 
-                for statement in statements.clone() {
-                    if Statement::Break == *statement {
-                        return Ok(None);
-                    }
+        let list = match self.get_literal(expr)? {
+            Literal::List(list) => list,
+            unexpected => return Err(format!("Cannot iterate over {}", unexpected).into()),
+        };
 
-                    self.push(id.clone(), *element.clone());
+        self.contexts.push(Context::Loop);
 
-                    match self.eval_statement(*statement) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            if e != "The break keyword cannot be used outside a loop!".to_string() {
-                                return Err(e);
-                            }
-                        }
-                    }
+        let result = self.eval_for_loop(list, statements, id_span);
 
-                    self.stack.last_mut().unwrap().remove(id.as_str());
-                }
-            }
+        self.contexts.pop();
+
+        result
+    }
+
+    fn eval_for_loop(
+        &mut self,
+        list: Rc<RefCell<Vec<Box<Literal>>>>,
+        statements: &[Box<Statement>],
+        id_span: &Span,
+    ) -> Result<ControlFlow> {
+        let mut index = 0;
+
+        loop {
+            let element = match list.borrow().get(index) {
+                Some(element) => *element.clone(),
+                None => break,
+            };
+
+            index += 1;
+            self.stack.push(Rc::new(RefCell::new(vec![Some(Variable {
+                literal: element,
+                span: id_span.clone(),
+            })])));
+
+            let result = self.eval_block(statements);
 
             self.stack.pop();
-            self.contexts.pop();
-            self.loops_conditions.pop();
+
+            match result? {
+                ControlFlow::Normal | ControlFlow::Continue => {}
+                ControlFlow::Break => break,
+                flow => return Ok(flow),
+            }
         }
 
-        Ok(None)
+        Ok(ControlFlow::Normal)
     }
 
-    fn eval_break(&mut self) -> Result<Option<Literal>, String> {
-        if self.contexts.contains(&Context::Loop) {
-            let last_index = self.loops_conditions.len() - 1;
-            self.loops_conditions[last_index] = Expr::Boolean(false);
-
-            return Ok(None);
+    fn eval_loop_jump(&mut self, keyword: &str, flow: ControlFlow) -> Result<ControlFlow> {
+        if self.contexts.last() == Some(&Context::Loop) {
+            return Ok(flow);
         }
 
-        Err("The break keyword cannot be used outside a loop!".to_string())
+        Err(format!("The {} keyword cannot be used outside a loop!", keyword).into())
     }
 
-    fn eval_load(&mut self, raw_file_path: String) -> Result<Option<Literal>, String> {
-        if raw_file_path == self.file_path.to_string_lossy().to_string() {
-            return Ok(None);
+    fn eval_load(&mut self, raw_file_path: &str, span: &Span) -> Result<ControlFlow> {
+        if raw_file_path == self.file_path.to_string_lossy() {
+            return Ok(ControlFlow::Normal);
         }
 
-        match run(&raw_file_path, self.cache) {
+        match run(raw_file_path, &self.modules, false, Backend::Interpreter) {
             Ok(Some(stack)) => {
-                for (id, value) in stack {
-                    self.push(id, value);
+                for variable in stack.values() {
+                    self.heap.adopt(&variable.literal);
                 }
+
+                self.globals.borrow_mut().extend(stack)
             }
             Ok(None) => {}
-            Err(e) => return Err(e),
+            Err(error) => {
+                return Err(error.with_frame(Frame {
+                    function: "<module>".to_string(),
+                    call_site: span.clone(),
+                }))
+            }
         }
 
-        Ok(None)
+        Ok(ControlFlow::Normal)
     }
 
     fn eval_control_flow(
         &mut self,
-        condition: Expr,
-        if_body: Vec<Box<Statement>>,
-        else_body: Vec<Box<Statement>>,
-    ) -> Result<Option<Literal>, String> {
-        for statement in if self.eval_condition(condition)? {
-            if_body
+        condition: &Expr,
+        if_body: &[Box<Statement>],
+        else_body: &[Box<Statement>],
+    ) -> Result<ControlFlow> {
+        if self.eval_condition(condition)? {
+            self.eval_block(if_body)
         } else {
-            else_body
-        } {
-            if let Some(result) = self.eval_statement(*statement.clone())? {
-                return Ok(Some(result));
+            self.eval_block(else_body)
+        }
+    }
+
+    pub fn eval_condition(&mut self, condition: &Expr) -> Result<bool> {
+        let literal = self.get_literal(condition)?;
+
+        Self::condition(literal)
+    }
+
+    pub fn condition(literal: Literal) -> Result<bool> {
+        match literal {
+            Literal::Boolean(boolean) => Ok(boolean),
+            unexpected => {
+                Err(format!("An expression must return a boolean, not `{}`", unexpected).into())
             }
         }
+    }
+
+    pub fn get_literal(&mut self, expr: &Expr) -> Result<Literal> {
+        let literal = self
+            .eval_expr_kind(expr)
+            .map_err(|error| error.or_span(&expr.span))?;
 
-        Ok(None)
+        self.hold(&literal);
+
+        Ok(literal)
     }
 
-    fn eval_condition(&mut self, condition: Expr) -> Result<bool, String> {
-        match self.get_literal(condition) {
-            Ok(Literal::Boolean(boolean)) => Ok(boolean),
-            Ok(unexpected) => Err(format!(
-                "An expression must return a boolean, not `{}`",
-                unexpected
-            )),
-            Err(error) => Err(error),
+    fn hold(&mut self, literal: &Literal) {
+        if Object::of(literal).is_some() {
+            self.temporaries.push(literal.clone());
         }
     }
 
-    pub fn get_literal(&mut self, expr: Expr) -> Result<Literal, String> {
-        Ok(match expr {
-            Expr::String(string) => Literal::String(string),
-            Expr::Int(int) => Literal::Int(int),
-            Expr::Float(float) => Literal::Float(float),
-            Expr::Boolean(boolean) => Literal::Boolean(boolean),
-            Expr::Var(id) => self.get_var(id)?,
-            Expr::Call(id, args) => self.eval_call(id, args)?,
-            Expr::List(list) => Literal::List(self.get_literals(list)?),
-            Expr::Index(list, index) => self.eval_index(list, index)?,
-            Expr::Dictionnary(dict) => {
+    fn eval_expr_kind(&mut self, expr: &Expr) -> Result<Literal> {
+        Ok(match &expr.kind {
+            ExprKind::String(string) => Literal::String(string.as_str().into()),
+            ExprKind::Int(int) => Literal::Int(*int),
+            ExprKind::BigInt(int) => Literal::BigInt(int.clone()),
+            ExprKind::Float(float) => Literal::Float(*float),
+            ExprKind::Boolean(boolean) => Literal::Boolean(*boolean),
+            ExprKind::Var(id, resolution) => self.get_var(id, resolution)?,
+            ExprKind::Call(function, args, named_args) => {
+                self.eval_call(function, args, named_args, &expr.span)?
+            }
+            ExprKind::List(list) => {
+                let list = Literal::list(self.get_literals(list)?);
+                self.heap.adopt(&list);
+
+                list
+            }
+            ExprKind::Index(list, index) => self.eval_index(list, index)?,
+            ExprKind::Lambda(params, statements) => {
+                Literal::Function(self.function("lambda", params, statements))
+            }
+            ExprKind::Dictionnary(dict) => {
                 let mut literals_dict: HashMap<String, Box<Literal>> = HashMap::new();
 
                 for (key, value) in dict {
-                    literals_dict.insert(key, Box::from(self.get_literal(*value)?));
+                    literals_dict.insert(key.clone(), Box::from(self.get_literal(value)?));
                 }
 
-                Literal::Dictionnary(literals_dict)
+                let dict = Literal::dictionnary(literals_dict);
+                self.heap.adopt(&dict);
+
+                dict
             }
-            Expr::BinOp(op, left, right) => self.eval_binary_op(op, left, right)?,
+            ExprKind::BinOp(op, left, right) => self.eval_binary_op(op, left, right)?,
+            ExprKind::Unary(op, expr) => self.eval_unary_op(op, expr)?,
         })
     }
 
-    fn get_literals(&mut self, list: Vec<Box<Expr>>) -> Result<Vec<Box<Literal>>, String> {
+    fn get_literals(&mut self, list: &[Box<Expr>]) -> Result<Vec<Box<Literal>>> {
         let mut literals: Vec<Box<Literal>> = vec![];
 
         for expr in list {
-            literals.push(Box::new(self.get_literal(*expr)?));
+            literals.push(Box::new(self.get_literal(expr)?));
         }
 
         Ok(literals)
     }
 
-    pub fn eval_call(&mut self, id: String, call_args: Vec<Box<Expr>>) -> Result<Literal, String> {
-        if self.builtins.contains_key(&id) {
-            Ok(self.builtins[&id](self, call_args)?)
-        } else {
-            for scope in self.stack.clone().iter().rev() {
-                if scope.contains_key(&id) {
-                    if let Literal::Function(_, args, statements) = &scope[&id] {
-                        if args.len() > call_args.len() {
-                            return Err(format!(
-                                "Not enought arguments for `{}`, expected `{}` given `{}`!",
-                                id,
-                                call_args.len(),
-                                args.len()
-                            ));
-                        }
-
-                        self.stack.push(HashMap::new());
-                        self.contexts.push(Context::Function);
-
-                        for index in 0..(args.len()) {
-                            let literal = self.get_literal(*(call_args[index].clone()))?;
-
-                            self.push(args[index].clone(), literal);
-                        }
-
-                        let mut return_literal = Literal::None;
-
-                        for statement in statements.clone() {
-                            if let Some(literal) = self.eval_statement(statement)? {
-                                return_literal = literal;
-                                break;
-                            }
-                        }
-
-                        self.stack.pop();
-                        self.contexts.pop();
-
-                        return Ok(return_literal);
+    pub fn eval_call(
+        &mut self,
+        function: &Expr,
+        call_args: &[Box<Expr>],
+        named_args: &[(String, Box<Expr>)],
+        call_site: &Span,
+    ) -> Result<Literal> {
+        let function = match &function.kind {
+            ExprKind::Var(id, resolution) => {
+                if let Some(index) = builtins::index(id) {
+                    let literals = self.get_builtin_args(id, call_args, named_args)?;
+                    let literal = BUILTINS[index].1(self, literals)?;
+                    self.heap.adopt(&literal);
+
+                    return Ok(literal);
+                }
+
+                match self.with_var(id, resolution, |variable| variable.literal.clone()) {
+                    Some(literal) => {
+                        self.hold(&literal);
+                        literal
+                    }
+                    None => {
+                        let error = FlushError::from(format!("Undefined function `{}`!", id));
+
+                        return Err(self.suggest(id, error));
                     }
                 }
             }
+            _ => self.get_literal(function)?,
+        };
+        let literals = self.get_literals(call_args)?;
+        let mut named_literals: Vec<(String, Literal)> = vec![];
 
-            Err(format!("Undefined function `{}`!", id))
+        for (id, expr) in named_args {
+            named_literals.push((id.clone(), self.get_literal(expr)?));
         }
+
+        self.call_function(
+            function,
+            literals.into_iter().map(|literal| *literal).collect(),
+            named_literals,
+            call_site.clone(),
+        )
     }
 
-    fn eval_index(&mut self, expr: Box<Expr>, index: Box<Expr>) -> Result<Literal, String> {
-        match self.get_literal(*expr)? {
-            Literal::String(string) => {
-                let index = self.get_index(&Vec::from(string.clone()), index)?;
-                Ok(self.eval_string_at(string, index)?)
+    fn get_builtin_args(
+        &mut self,
+        id: &str,
+        call_args: &[Box<Expr>],
+        named_args: &[(String, Box<Expr>)],
+    ) -> Result<Vec<Literal>> {
+        if !named_args.is_empty() {
+            return Err(format!("`{}` does not accept named arguments!", id).into());
+        }
+
+        Ok(self
+            .get_literals(call_args)?
+            .into_iter()
+            .map(|literal| *literal)
+            .collect())
+    }
+
+    pub fn call_function(
+        &mut self,
+        function: Literal,
+        literals: Vec<Literal>,
+        named_literals: Vec<(String, Literal)>,
+        call_site: Span,
+    ) -> Result<Literal> {
+        let function = match function {
+            Literal::Function(function) => function,
+            Literal::Host(host) => {
+                if !named_literals.is_empty() {
+                    return Err(format!("`{}` does not accept named arguments!", host.name).into());
+                }
+
+                return host.call(self, literals);
             }
-            Literal::List(list) => {
-                let index = self.get_index(&list, index)?;
-                Ok(self.eval_list_at(list, index)?)
-            }
-            Literal::Dictionnary(dict) => match self.get_literal(*index)? {
-                Literal::String(key) => {
-                    if let Some(value) = dict.get(&key) {
-                        Ok(*value.clone())
-                    } else {
-                        return Err(format!("The key `{}` does not exist in `{:?}`", key, dict));
-                    }
+            unexpected => return Err(format!("`{}` is not a function!", unexpected).into()),
+        };
+
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(FlushError::from(format!(
+                "Maximum call depth of `{}` exceeded while calling `{}`!",
+                MAX_CALL_DEPTH, function.name
+            ))
+            .with_help("check that the recursion has a reachable base case"));
+        }
+
+        let caller_stack = std::mem::replace(&mut self.stack, function.scopes.clone());
+        let caller_globals = std::mem::replace(&mut self.globals, function.globals.clone());
+        self.callers.push((caller_stack, caller_globals));
+
+        self.push_scope();
+        self.contexts.push(Context::Function);
+        self.frames.push(Frame {
+            function: function.name.clone(),
+            call_site,
+        });
+
+        let result =
+            match self.bind_args(&function.name, &function.params, literals, named_literals) {
+                Ok(()) => self
+                    .eval_function_body(&function.body)
+                    .map_err(|error| error.with_frame(self.frames.last().unwrap().clone())),
+                Err(error) => Err(error),
+            };
+
+        (self.stack, self.globals) = self.callers.pop().unwrap();
+        self.contexts.pop();
+        self.frames.pop();
+
+        result
+    }
+
+    fn bind_args(
+        &mut self,
+        id: &str,
+        args: &[Param],
+        literals: Vec<Literal>,
+        named_literals: Vec<(String, Literal)>,
+    ) -> Result<()> {
+        let bound = Self::bind(id, args, literals, named_literals)?;
+
+        for (slot, (arg, literal)) in args.iter().zip(bound).enumerate() {
+            let literal = match (&arg.kind, literal) {
+                (ParamKind::Rest(_), Some(literal)) => {
+                    self.heap.adopt(&literal);
+                    literal
                 }
-                unexpected => {
+                (_, Some(literal)) => literal,
+                (ParamKind::Optional(_, default), None) => self.get_literal(default)?,
+                (_, None) => unreachable!(),
+            };
+
+            self.define(
+                arg.name(),
+                &Resolution::Local(0, slot),
+                literal,
+                arg.span.clone(),
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn bind(
+        id: &str,
+        args: &[Param],
+        literals: Vec<Literal>,
+        named_literals: Vec<(String, Literal)>,
+    ) -> Result<Vec<Option<Literal>>> {
+        let given = literals.len() + named_literals.len();
+        let has_rest = matches!(args.last().map(|arg| &arg.kind), Some(ParamKind::Rest(_)));
+        let positional_count = if has_rest { args.len() - 1 } else { args.len() };
+        let mut bound: Vec<Option<Literal>> = vec![None; args.len()];
+        let mut rest: Vec<Box<Literal>> = vec![];
+
+        for (index, literal) in literals.into_iter().enumerate() {
+            if index < positional_count {
+                bound[index] = Some(literal);
+            } else if has_rest {
+                rest.push(Box::new(literal));
+            } else {
+                return Err(format!(
+                    "Too many arguments for `{}`, expected `{}` given `{}`!",
+                    id,
+                    Self::arity(args),
+                    given
+                )
+                .into());
+            }
+        }
+
+        for (name, literal) in named_literals {
+            match args[..positional_count]
+                .iter()
+                .position(|arg| arg.name() == name)
+            {
+                Some(index) if bound[index].is_some() => {
+                    return Err(
+                        format!("The argument `{}` of `{}` is given twice!", name, id).into(),
+                    )
+                }
+                Some(index) => bound[index] = Some(literal),
+                None => return Err(format!("`{}` has no parameter named `{}`!", id, name).into()),
+            }
+        }
+
+        for (arg, literal) in args.iter().zip(bound.iter_mut()) {
+            match (&arg.kind, &literal) {
+                (ParamKind::Rest(_), None) => {
+                    *literal = Some(Literal::list(std::mem::take(&mut rest)))
+                }
+                (ParamKind::Required(name), None) => {
                     return Err(format!(
-                        "Could not index `Dictionnary` with {:?}",
-                        unexpected
-                    ))
+                        "Not enough arguments for `{}`, expected `{}` given `{}`: `{}` is missing!",
+                        id,
+                        Self::arity(args),
+                        given,
+                        name
+                    )
+                    .into())
                 }
-            },
-            unexpected => Err(format!("Could not index {:?}", unexpected)),
+                _ => {}
+            }
+        }
+
+        Ok(bound)
+    }
+
+    fn arity(args: &[Param]) -> String {
+        let required = args
+            .iter()
+            .filter(|arg| matches!(arg.kind, ParamKind::Required(_)))
+            .count();
+        let optional = args
+            .iter()
+            .filter(|arg| matches!(arg.kind, ParamKind::Optional(_, _)))
+            .count();
+
+        if matches!(args.last().map(|arg| &arg.kind), Some(ParamKind::Rest(_))) {
+            format!("at least {}", required)
+        } else if optional > 0 {
+            format!("{} to {}", required, required + optional)
+        } else {
+            required.to_string()
         }
     }
 
-    fn eval_string_at(&mut self, string: String, index: usize) -> Result<Literal, String> {
-        Ok(Literal::String(
-            string.chars().nth(index).unwrap().to_string(),
-        ))
+    fn eval_function_body(&mut self, statements: &[Statement]) -> Result<Literal> {
+        for statement in statements {
+            if let ControlFlow::Return(literal) = self.eval_statement(statement)? {
+                return Ok(literal);
+            }
+        }
+
+        Ok(Literal::None)
+    }
+
+    fn eval_index(&mut self, expr: &Expr, index: &Expr) -> Result<Literal> {
+        let literal = self.get_literal(expr)?;
+        let index = self.get_literal(index)?;
+
+        Self::index(literal, index)
     }
 
-    fn eval_list_at(&mut self, list: Vec<Box<Literal>>, index: usize) -> Result<Literal, String> {
-        Ok(*list[index].clone())
+    pub fn index(literal: Literal, index: Literal) -> Result<Literal> {
+        match literal {
+            Literal::String(string) => {
+                let chars = string.chars().collect::<Vec<_>>();
+                let index = Self::get_index(&chars, index)?;
+                Ok(Literal::String(chars[index].to_string().into()))
+            }
+            Literal::List(list) => {
+                let list = list.borrow();
+                let index = Self::get_index(&list, index)?;
+                Ok(*list[index].clone())
+            }
+            Literal::Dictionnary(dict) => match index {
+                Literal::String(key) => match dict.borrow().get(&*key) {
+                    Some(value) => Ok(*value.clone()),
+                    None => Err(format!(
+                        "The key `{}` does not exist in `{}`",
+                        key,
+                        Literal::Dictionnary(dict.clone())
+                    )
+                    .into()),
+                },
+                unexpected => {
+                    Err(format!("Could not index `Dictionnary` with {}", unexpected).into())
+                }
+            },
+            unexpected => Err(format!("Could not index {}", unexpected).into()),
+        }
     }
 
-    fn get_index<T>(&mut self, list: &Vec<T>, index: Box<Expr>) -> Result<usize, String> {
-        let index = match self.get_literal(*index)? {
+    fn get_index<T>(list: &[T], index: Literal) -> Result<usize> {
+        let index = match index {
             Literal::Int(int) => int,
-            unexpected => return Err(format!("Expected Integer found `{:?}`", unexpected)),
+            Literal::BigInt(int) => {
+                return Err(format!("The `{}` is not valid as an index!", int).into())
+            }
+            unexpected => return Err(format!("Expected Integer found `{}`", unexpected).into()),
         };
 
         if index < 0 {
-            return Err(format!("The `{}` is not valid as an index!", index));
+            return Err(format!("The `{}` is not valid as an index!", index).into());
         }
 
         let index = index as usize;
@@ -473,64 +848,207 @@ impl<'a> Interpreter<'a> {
                 "The list has a length of `{}` but the index is `{}`!",
                 list.len(),
                 index
-            ));
+            )
+            .into());
         }
 
         Ok(index)
     }
 
-    fn eval_binary_op(
-        &mut self,
-        op: BinOp,
-        left: Box<Expr>,
-        right: Box<Expr>,
-    ) -> Result<Literal, String> {
-        Ok(match op {
-            BinOp::Add => self.add(left, right)?,
-            BinOp::Sub => self.sub(left, right)?,
-            BinOp::Mul => self.mul(left, right)?,
-            BinOp::Div => self.div(left, right, false)?,
-            BinOp::FloorDiv => self.div(left, right, true)?,
-            BinOp::Mod => self.r#mod(left, right)?,
-            BinOp::Pow => self.pow(left, right)?,
-            BinOp::Lt => self.lt(left, right)?,
-            BinOp::Gt => self.gt(left, right)?,
-            BinOp::Eq => self.eq(left, right)?,
-            BinOp::Ne => self.ne(left, right)?,
-            BinOp::Le => self.le(left, right)?,
-            BinOp::Ge => self.ge(left, right)?,
-        })
+    fn eval_binary_op(&mut self, op: &BinOp, left: &Expr, right: &Expr) -> Result<Literal> {
+        match op {
+            BinOp::And => self.and(left, right),
+            BinOp::Or => self.or(left, right),
+            op => {
+                let left = self.get_literal(left)?;
+                let right = self.get_literal(right)?;
+                let literal = Literal::binary(op, left, right)?;
+                self.heap.adopt(&literal);
+
+                Ok(literal)
+            }
+        }
     }
 
-    pub fn get_var(&self, id: String) -> Result<Literal, String> {
-        for scope in self.stack.iter().rev() {
-            if scope.contains_key(&id) {
-                return Ok(scope[&id].clone());
+    fn eval_unary_op(&mut self, op: &UnaryOp, expr: &Expr) -> Result<Literal> {
+        match op {
+            UnaryOp::Neg => Literal::negate(self.get_literal(expr)?),
+            UnaryOp::Not => self.not(expr),
+        }
+    }
+
+    fn with_var<T>(
+        &self,
+        id: &str,
+        resolution: &Resolution,
+        f: impl FnOnce(&Variable) -> T,
+    ) -> Option<T> {
+        if let Resolution::Local(depth, slot) = resolution {
+            if let Some(Some(variable)) = self.frame(*depth).borrow().get(*slot) {
+                return Some(f(variable));
             }
         }
 
-        Err(format!("The variable `{}` cannot be found!", id))
+        self.globals.borrow().get(id).map(f)
+    }
+
+    pub fn already_exists(id: &str, span: &Span) -> FlushError {
+        FlushError::from(format!("The `{}` variable already exists!", id))
+            .with_label(span.clone(), format!("`{}` is first defined here", id))
+    }
+
+    fn suggest(&self, id: &str, error: FlushError) -> FlushError {
+        let globals = self.globals.borrow();
+        let names = globals
+            .keys()
+            .map(|name| name.as_str())
+            .chain(builtins::names());
+
+        match similar_name(id, names) {
+            Some(name) => error.with_help(format!("a similar name exists: `{}`", name)),
+            None => error,
+        }
     }
 
-    pub fn set_var(&mut self, id: String, value: Literal) -> Result<Option<Literal>, String> {
-        self.get_var(id.clone())?;
+    fn not_found(&self, id: &str) -> FlushError {
+        let error = FlushError::from(format!("The variable `{}` cannot be found!", id));
 
-        for stack in self.stack.iter_mut().rev() {
-            if stack.clone().contains_key(&*id) {
-                stack.insert(id, value);
-                return Ok(None);
+        self.suggest(id, error)
+    }
+
+    pub fn get_var(&self, id: &str, resolution: &Resolution) -> Result<Literal> {
+        self.with_var(id, resolution, |variable| variable.literal.clone())
+            .ok_or_else(|| self.not_found(id))
+    }
+
+    pub fn set_var(&mut self, id: &str, resolution: &Resolution, value: Literal) -> Result<()> {
+        if let Resolution::Local(depth, slot) = resolution {
+            if let Some(Some(variable)) = self.frame(*depth).borrow_mut().get_mut(*slot) {
+                variable.literal = value;
+                return Ok(());
             }
         }
 
-        unreachable!()
+        if let Some(variable) = self.globals.borrow_mut().get_mut(id) {
+            variable.literal = value;
+            return Ok(());
+        }
+
+        Err(self.not_found(id))
+    }
+
+    pub fn load(&mut self, raw_file_path: &str) -> Result<()> {
+        self.eval_load(raw_file_path, &Span::default())?;
+
+        Ok(())
     }
 
-    pub fn interpret(&mut self) -> Result<(), String> {
-        while !self.is_at_end() {
-            let statement = self.advance().unwrap();
-            self.eval_statement(statement)?;
+    pub fn eval_input(&mut self, mut statements: Vec<Statement>) -> Result<Option<Literal>> {
+        let mut value = None;
+
+        Resolver::resolve(&mut statements);
+
+        for statement in &statements {
+            value = match &statement.kind {
+                StatementKind::Expr(expr) => {
+                    let temporaries = self.temporaries.len();
+                    let literal = self.get_literal(expr);
+
+                    self.temporaries.truncate(temporaries);
+                    Some(literal?)
+                }
+                _ => match self.eval_statement(statement)? {
+                    ControlFlow::Return(literal) => return Ok(Some(literal)),
+                    _ => None,
+                },
+            };
+        }
+
+        Ok(value)
+    }
+
+    fn collect_garbage(&mut self) -> usize {
+        let mut roots = vec![Object::Scope(Rc::downgrade(&self.globals))];
+
+        for (stack, globals) in &self.callers {
+            roots.extend(
+                stack
+                    .iter()
+                    .map(|scope| Object::Slots(Rc::downgrade(scope))),
+            );
+            roots.push(Object::Scope(Rc::downgrade(globals)));
+        }
+
+        roots.extend(
+            self.stack
+                .iter()
+                .map(|scope| Object::Slots(Rc::downgrade(scope))),
+        );
+        roots.extend(self.temporaries.iter().filter_map(Object::of));
+        roots.extend(self.modules.borrow().roots());
+
+        self.heap.collect(roots)
+    }
+
+    pub fn interpret(&mut self) -> Result<()> {
+        let statements = std::mem::take(&mut self.statements);
+
+        // A top-level `return` ends the script, like the end of the file
+        for statement in &statements {
+            if let ControlFlow::Return(_) = self.eval_statement(statement)? {
+                break;
+            }
         }
 
         Ok(())
     }
 }
+
+impl Runtime for Interpreter {
+    fn heap(&mut self) -> &mut Heap {
+        &mut self.heap
+    }
+
+    fn collect(&mut self) -> usize {
+        self.collect_garbage()
+    }
+
+    fn get_args(&self) -> Literal {
+        Literal::list(
+            self.args
+                .iter()
+                .map(|arg| Box::new(Literal::String(arg.as_str().into())))
+                .collect(),
+        )
+    }
+
+    fn call(&mut self, function: Literal, args: Vec<Literal>) -> Result<Literal> {
+        self.call_function(function, args, vec![], Span::default())
+    }
+
+    fn get_global(&self, id: &str) -> Option<Literal> {
+        self.globals
+            .borrow()
+            .get(id)
+            .map(|variable| variable.literal.clone())
+    }
+
+    fn set_global(&mut self, id: String, literal: Literal) {
+        self.heap.adopt(&literal);
+
+        let mut globals = self.globals.borrow_mut();
+
+        match globals.get_mut(&id) {
+            Some(variable) => variable.literal = literal,
+            None => {
+                globals.insert(
+                    id,
+                    Variable {
+                        literal,
+                        span: Span::default(),
+                    },
+                );
+            }
+        }
+    }
+}
@@ -1,3 +1,10 @@
 pub mod builtins;
+pub mod conversions;
+pub mod gc;
+pub mod host;
 pub mod interpreter;
+pub mod modules;
+pub mod resolver;
+#[cfg(test)]
+mod tests;
 pub mod typing;
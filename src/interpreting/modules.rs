@@ -0,0 +1,59 @@
+use crate::interpreting::gc::Object;
+use crate::interpreting::typing::Variable;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+pub type Modules = Rc<RefCell<ModuleRegistry>>;
+
+enum Module {
+    Loading,
+    Loaded(HashMap<String, Variable>),
+}
+
+#[derive(Default)]
+pub struct ModuleRegistry {
+    modules: HashMap<PathBuf, Module>,
+}
+
+impl ModuleRegistry {
+    pub fn shared() -> Modules {
+        Rc::new(RefCell::new(Self::default()))
+    }
+
+    pub fn is_loading(&self, file_path: &Path) -> bool {
+        matches!(self.modules.get(file_path), Some(Module::Loading))
+    }
+
+    pub fn exports(&self, file_path: &Path) -> Option<HashMap<String, Variable>> {
+        match self.modules.get(file_path) {
+            Some(Module::Loaded(globals)) => Some(globals.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn start(&mut self, file_path: PathBuf) {
+        self.modules.insert(file_path, Module::Loading);
+    }
+
+    pub fn finish(&mut self, file_path: PathBuf, globals: HashMap<String, Variable>) {
+        self.modules.insert(file_path, Module::Loaded(globals));
+    }
+
+    // Loaded modules are handed to every file that loads them again, so what
+    // they export stays in use
+    pub fn roots(&self) -> Vec<Object> {
+        self.modules
+            .values()
+            .flat_map(|module| match module {
+                Module::Loaded(globals) => Object::variables(globals.values()),
+                Module::Loading => vec![],
+            })
+            .collect()
+    }
+
+    pub fn forget(&mut self, file_path: &Path) {
+        self.modules.remove(file_path);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::error::Span;
+use crate::parsing::typing::*;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+struct Block {
+    slots: HashMap<String, usize>,
+    // The names defined so far, including in the `if` statements already passed
+    defined: HashSet<String>,
+    function: bool,
+}
+
+impl Block {
+    // The first `bound` slots hold the parameters or the loop variable
+    fn new(layout: Vec<(String, Span)>, bound: usize, function: bool) -> Self {
+        let defined = layout[..bound].iter().map(|(id, _)| id.clone()).collect();
+        let slots = layout
+            .into_iter()
+            .enumerate()
+            .map(|(slot, (id, _))| (id, slot))
+            .collect();
+
+        Self {
+            slots,
+            defined,
+            function,
+        }
+    }
+}
+
+// The slots of a block in order: the parameters or loop variable it starts with, then
+// every name defined in its body, including the bodies of its `if` statements
+pub fn layout<'a>(
+    mut slots: Vec<(String, Span)>,
+    statements: impl IntoIterator<Item = &'a Statement>,
+) -> Vec<(String, Span)> {
+    for statement in statements {
+        match &statement.kind {
+            StatementKind::VarDef(id, _, _) | StatementKind::FuncDef(id, _, _, _)
+                if !slots.iter().any(|(name, _)| name == id) =>
+            {
+                slots.push((id.clone(), statement.span.clone()));
+            }
+            StatementKind::If(_, if_body, else_body) => {
+                slots = layout(slots, if_body.iter().map(Box::as_ref));
+                slots = layout(slots, else_body.iter().map(Box::as_ref));
+            }
+            _ => {}
+        }
+    }
+
+    slots
+}
+
+#[derive(Default)]
+pub struct Resolver {
+    blocks: Vec<Block>,
+}
+
+impl Resolver {
+    pub fn resolve(statements: &mut [Statement]) {
+        let mut resolver = Self::default();
+
+        for statement in statements {
+            resolver.statement(statement);
+        }
+    }
+
+    // A block's slot is only visible from its definition onward in the same function, so
+    // an outer binding stays visible until then. Nested functions may run before or after
+    // the definition, so they see the innermost slot and fall back to the globals while it
+    // is not bound yet
+    fn lookup(&self, id: &str) -> Resolution {
+        let mut enclosing = false;
+
+        for (depth, block) in self.blocks.iter().rev().enumerate() {
+            if let Some(slot) = block.slots.get(id) {
+                if enclosing || block.defined.contains(id) {
+                    return Resolution::Local(depth, *slot);
+                }
+            }
+
+            enclosing |= block.function;
+        }
+
+        Resolution::Global
+    }
+
+    fn define(&mut self, id: &str) -> Resolution {
+        match self.blocks.last_mut() {
+            Some(block) => {
+                block.defined.insert(id.to_string());
+                Resolution::Local(0, block.slots[id])
+            }
+            None => Resolution::Global,
+        }
+    }
+
+    fn scoped_block(&mut self, slots: Vec<(String, Span)>, statements: &mut [Box<Statement>]) {
+        let bound = slots.len();
+        let layout = layout(slots, statements.iter().map(Box::as_ref));
+        self.blocks.push(Block::new(layout, bound, false));
+
+        for statement in statements {
+            self.statement(statement);
+        }
+
+        self.blocks.pop();
+    }
+
+    fn function(&mut self, params: &mut [Param], statements: &mut [Statement]) {
+        let slots = params
+            .iter()
+            .map(|param| (param.name().to_string(), param.span.clone()))
+            .collect();
+
+        let layout = layout(slots, statements.iter());
+        self.blocks.push(Block::new(layout, params.len(), true));
+
+        for param in params {
+            if let ParamKind::Optional(_, default) = &mut param.kind {
+                self.expr(default);
+            }
+        }
+
+        for statement in statements {
+            self.statement(statement);
+        }
+
+        self.blocks.pop();
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        match &mut statement.kind {
+            StatementKind::If(condition, if_body, else_body) => {
+                self.expr(condition);
+
+                for statement in if_body.iter_mut().chain(else_body.iter_mut()) {
+                    self.statement(statement);
+                }
+            }
+            StatementKind::VarDef(id, expr, resolution) => {
+                self.expr(expr);
+                *resolution = self.define(id);
+            }
+            StatementKind::FuncDef(id, params, statements, resolution) => {
+                *resolution = self.define(id);
+                self.function(params, Rc::make_mut(statements));
+            }
+            StatementKind::VarSet(id, expr, resolution) => {
+                self.expr(expr);
+                *resolution = self.lookup(id);
+            }
+            StatementKind::IndexSet(expr, index, value) => {
+                self.expr(value);
+                self.expr(expr);
+                self.expr(index);
+            }
+            StatementKind::Return(expr) | StatementKind::Expr(expr) => self.expr(expr),
+            StatementKind::While(condition, statements) => {
+                self.expr(condition);
+                self.scoped_block(vec![], statements);
+            }
+            StatementKind::For(id, id_span, list, statements, resolution) => {
+                *resolution = self.lookup(id);
+                self.expr(list);
+
+                let slots = vec![(id.clone(), id_span.clone())];
+                self.scoped_block(slots, statements);
+            }
+            StatementKind::Break | StatementKind::Continue | StatementKind::Load(_) => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match &mut expr.kind {
+            ExprKind::String(_)
+            | ExprKind::Int(_)
+            | ExprKind::BigInt(_)
+            | ExprKind::Float(_)
+            | ExprKind::Boolean(_) => {}
+            ExprKind::Var(id, resolution) => *resolution = self.lookup(id),
+            ExprKind::Call(function, args, named_args) => {
+                self.expr(function);
+
+                for arg in args {
+                    self.expr(arg);
+                }
+
+                for (_, arg) in named_args {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::List(list) => {
+                for element in list {
+                    self.expr(element);
+                }
+            }
+            ExprKind::Dictionnary(dict) => {
+                for value in dict.values_mut() {
+                    self.expr(value);
+                }
+            }
+            ExprKind::Index(list, index) => {
+                self.expr(list);
+                self.expr(index);
+            }
+            ExprKind::Lambda(params, statements) => self.function(params, Rc::make_mut(statements)),
+            ExprKind::BinOp(_, left, right) => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary(_, expr) => self.expr(expr),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::flush::parse;
+use crate::interpreting::resolver::Resolver;
+use crate::parsing::typing::*;
+use std::path::PathBuf;
+
+// Lists every name the resolver annotated, in source order
+fn resolutions(source: &str) -> Vec<(String, Resolution)> {
+    let mut statements = parse(source, PathBuf::from("<test>")).unwrap();
+    Resolver::resolve(&mut statements);
+
+    let mut resolutions = vec![];
+    walk(statements.iter(), &mut resolutions);
+
+    resolutions
+}
+
+fn walk<'a>(
+    statements: impl IntoIterator<Item = &'a Statement>,
+    resolutions: &mut Vec<(String, Resolution)>,
+) {
+    for statement in statements {
+        match &statement.kind {
+            StatementKind::If(condition, if_body, else_body) => {
+                expr(condition, resolutions);
+                walk(if_body.iter().map(Box::as_ref), resolutions);
+                walk(else_body.iter().map(Box::as_ref), resolutions);
+            }
+            StatementKind::VarDef(id, value, resolution)
+            | StatementKind::VarSet(id, value, resolution) => {
+                expr(value, resolutions);
+                resolutions.push((id.clone(), *resolution));
+            }
+            StatementKind::FuncDef(id, _, body, resolution) => {
+                resolutions.push((id.clone(), *resolution));
+                walk(body.iter(), resolutions);
+            }
+            StatementKind::While(condition, body) => {
+                expr(condition, resolutions);
+                walk(body.iter().map(Box::as_ref), resolutions);
+            }
+            StatementKind::For(id, _, list, body, resolution) => {
+                resolutions.push((id.clone(), *resolution));
+                expr(list, resolutions);
+                walk(body.iter().map(Box::as_ref), resolutions);
+            }
+            StatementKind::Return(value) | StatementKind::Expr(value) => expr(value, resolutions),
+            _ => {}
+        }
+    }
+}
+
+fn expr(expr: &Expr, resolutions: &mut Vec<(String, Resolution)>) {
+    match &expr.kind {
+        ExprKind::Var(id, resolution) => resolutions.push((id.clone(), *resolution)),
+        ExprKind::Call(function, args, _) => {
+            self::expr(function, resolutions);
+
+            for arg in args {
+                self::expr(arg, resolutions);
+            }
+        }
+        ExprKind::Lambda(_, body) => walk(body.iter(), resolutions),
+        ExprKind::BinOp(_, left, right) => {
+            self::expr(left, resolutions);
+            self::expr(right, resolutions);
+        }
+        _ => {}
+    }
+}
+
+fn local(id: &str, depth: usize, slot: usize) -> (String, Resolution) {
+    (id.to_string(), Resolution::Local(depth, slot))
+}
+
+fn global(id: &str) -> (String, Resolution) {
+    (id.to_string(), Resolution::Global)
+}
+
+#[test]
+fn slots() {
+    assert_eq!(
+        resolutions("def f(a, b) { def c = a if (b) { def d = c } return d }"),
+        vec![
+            global("f"),
+            local("a", 0, 0),
+            local("c", 0, 2),
+            local("b", 0, 1),
+            local("c", 0, 2),
+            local("d", 0, 3),
+            local("d", 0, 3),
+        ]
+    );
+    assert_eq!(
+        resolutions("def f(n) { for (i in n) { def j = i while (j) { def k = n } } }"),
+        vec![
+            global("f"),
+            global("i"),
+            local("n", 0, 0),
+            local("i", 0, 0),
+            local("j", 0, 1),
+            local("j", 0, 1),
+            local("n", 2, 0),
+            local("k", 0, 0),
+        ]
+    );
+}
+
+#[test]
+fn definitions() {
+    assert_eq!(
+        resolutions("def f() { def x = 1 while (x) { printLn(x) def x = x + 1 printLn(x) } }"),
+        vec![
+            global("f"),
+            local("x", 0, 0),
+            local("x", 0, 0),
+            global("printLn"),
+            local("x", 1, 0),
+            local("x", 1, 0),
+            local("x", 0, 0),
+            global("printLn"),
+            local("x", 0, 0),
+        ]
+    );
+    assert_eq!(
+        resolutions("def f() { printLn(x) def x = 1 }"),
+        vec![
+            global("f"),
+            global("printLn"),
+            global("x"),
+            local("x", 0, 0),
+        ]
+    );
+}
+
+#[test]
+fn shadowed() {
+    assert_eq!(
+        resolutions("def f() { def x = 1 while (x) { if (x) { def x = 2 } x = 3 } }"),
+        vec![
+            global("f"),
+            local("x", 0, 0),
+            local("x", 0, 0),
+            local("x", 1, 0),
+            local("x", 0, 0),
+            local("x", 0, 0),
+        ]
+    );
+    assert_eq!(
+        resolutions("def f() { def g = def () { return x } def x = 1 }"),
+        vec![
+            global("f"),
+            local("x", 1, 1),
+            local("g", 0, 0),
+            local("x", 0, 1),
+        ]
+    );
+    assert_eq!(
+        resolutions("def f(x) { while (x) { def g = def () { return x } def x = 2 } }"),
+        vec![
+            global("f"),
+            local("x", 0, 0),
+            local("x", 1, 1),
+            local("g", 0, 0),
+            local("x", 0, 1),
+        ]
+    );
+    assert_eq!(
+        resolutions("def f(x) { def g() { return x } def h() { def x = 1 return g } }"),
+        vec![
+            global("f"),
+            local("g", 0, 1),
+            local("x", 1, 0),
+            local("h", 0, 2),
+            local("x", 0, 0),
+            local("g", 1, 1),
+        ]
+    );
+}
@@ -1,63 +1,155 @@
-use crate::parsing::typing::Statement;
+use crate::compiling::typing::Closure;
+use crate::error::Span;
+use crate::interpreting::host::HostFunction;
+use crate::parsing::typing::{Param, ParamKind, Statement};
+use num_bigint::BigInt;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
+use std::rc::Rc;
+
+pub type Scope = Rc<RefCell<HashMap<String, Variable>>>;
+pub type Slots = Rc<RefCell<Vec<Option<Variable>>>>;
+
+#[derive(Clone, Debug)]
+pub struct Variable {
+    pub literal: Literal,
+    pub span: Span,
+}
 
 #[derive(Clone, Debug)]
 pub enum Literal {
-    String(String),
-    Int(i32),
+    String(Rc<str>),
+    Int(i64),
+    BigInt(BigInt),
     Float(f64),
     Boolean(bool),
-    List(Vec<Box<Literal>>),
-    Dictionnary(HashMap<String, Box<Literal>>),
-    Function(String, Vec<String>, Vec<Statement>),
+    List(Rc<RefCell<Vec<Box<Literal>>>>),
+    Dictionnary(Rc<RefCell<HashMap<String, Box<Literal>>>>),
+    Function(Rc<Function>),
+    Closure(Rc<Closure>),
+    Host(Rc<HostFunction>),
     None,
 }
 
+impl Literal {
+    pub fn list(list: Vec<Box<Literal>>) -> Self {
+        Literal::List(Rc::new(RefCell::new(list)))
+    }
+
+    pub fn dictionnary(dict: HashMap<String, Box<Literal>>) -> Self {
+        Literal::Dictionnary(Rc::new(RefCell::new(dict)))
+    }
+}
+
+pub struct Function {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: Rc<[Statement]>,
+    pub scopes: Vec<Slots>,
+    pub globals: Scope,
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Function({})", self.name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ControlFlow {
+    Normal,
+    Break,
+    Continue,
+    Return(Literal),
+}
+
 #[derive(Clone, PartialEq)]
 pub enum Context {
     Function,
     TopLevel,
+    Repl,
     Loop,
 }
 
 impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, &mut vec![])
+    }
+}
+
+impl Literal {
+    // `printing` holds the lists and dictionaries being written, so that one containing
+    // itself is written as `[...]` or `{...}` instead of recursing forever
+    fn write(&self, f: &mut fmt::Formatter, printing: &mut Vec<*const ()>) -> fmt::Result {
         match self {
             Literal::String(string) => write!(f, "\"{}\"", string),
             Literal::Int(int) => write!(f, "{}", int),
+            Literal::BigInt(int) => write!(f, "{}", int),
             Literal::Float(float) => write!(f, "{}", float),
             Literal::Boolean(boolean) => write!(f, "{}", boolean),
             Literal::List(list) => {
+                let pointer = Rc::as_ptr(list) as *const ();
+
+                if printing.contains(&pointer) {
+                    return write!(f, "[...]");
+                }
+
+                printing.push(pointer);
                 write!(f, "[")?;
 
-                for (i, expr) in list.into_iter().enumerate() {
-                    if i == list.len() - 1 {
-                        write!(f, "{}", expr)?;
-                    } else {
-                        write!(f, "{}, ", expr)?;
+                for (i, literal) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
                     }
+
+                    literal.write(f, printing)?;
                 }
 
+                printing.pop();
                 write!(f, "]")
             }
             Literal::Dictionnary(dict) => {
+                let pointer = Rc::as_ptr(dict) as *const ();
+
+                if printing.contains(&pointer) {
+                    return write!(f, "{{...}}");
+                }
+
+                printing.push(pointer);
                 write!(f, "{{")?;
 
-                for (i, key) in dict.keys().enumerate() {
-                    if i == dict.keys().len() - 1 {
-                        write!(f, "\"{}\": {}", key, dict.get(key).unwrap())?;
-                    } else {
-                        write!(f, "\"{}\": {}, ", key, dict.get(key).unwrap())?;
+                for (i, (key, literal)) in dict.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
                     }
+
+                    write!(f, "\"{}\": ", key)?;
+                    literal.write(f, printing)?;
                 }
 
+                printing.pop();
                 write!(f, "}}")
             }
-            Literal::Function(name, args, _) => {
-                write!(f, "<function:{}#{}>", name, args.join(", "))
+            Literal::Function(function) => write_function(f, &function.name, &function.params),
+            Literal::Closure(closure) => {
+                write_function(f, &closure.function.name, &closure.function.params)
             }
+            Literal::Host(host) => write_function(f, &host.name, &[]),
             Literal::None => write!(f, "None"),
         }
     }
 }
+
+fn write_function(f: &mut fmt::Formatter, name: &str, args: &[Param]) -> fmt::Result {
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| match &arg.kind {
+            ParamKind::Required(name) => name.clone(),
+            ParamKind::Optional(name, _) => format!("{}=", name),
+            ParamKind::Rest(name) => format!("...{}", name),
+        })
+        .collect();
+
+    write!(f, "<function:{}#{}>", name, args.join(", "))
+}
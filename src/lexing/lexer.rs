@@ -1,24 +1,32 @@
 use super::typing::*;
-use crate::error::{FlushError, Result};
+use crate::error::{FlushError, Result, SourceFile, Span};
+use num_bigint::BigInt;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 #[derive(Default)]
 pub struct Lexer<'a> {
     program: &'a str,
-    file_path: PathBuf,
+    file: Rc<SourceFile>,
     tokens: Vec<Token>,
+    start: usize,
     position: usize,
     line: usize,
+    line_start: usize,
+    keep_comments: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(program: &'a str, file_path: PathBuf) -> Self {
         Self {
             program,
-            file_path,
+            file: Rc::new(SourceFile::new(file_path, program)),
             tokens: vec![],
+            start: 0,
             position: 0,
             line: 1,
+            line_start: 0,
+            keep_comments: false,
         }
     }
 
@@ -39,19 +47,47 @@ impl<'a> Lexer<'a> {
         self.position >= self.program.len()
     }
 
+    pub fn with_comments(mut self) -> Self {
+        self.keep_comments = true;
+        self
+    }
+
+    fn span(&self) -> Span {
+        let column = self.start - self.line_start + 1;
+        let length = self.position - self.start;
+
+        Span {
+            file: self.file.clone(),
+            line: self.line,
+            column,
+            length,
+            end_line: self.line,
+            end_column: column + length,
+        }
+    }
+
     fn push_token(&mut self, kind: TokenKind) {
         self.tokens.push(Token {
-            line: self.line,
+            span: self.span(),
             kind,
         });
     }
 
     fn skip_comment(&mut self) {
-        while !self.is_at_end() && self.advance() != Some('\n') {
+        while !self.is_at_end() && self.current() != Some('\n') {
             self.position += 1;
         }
 
-        self.line += 1;
+        if self.keep_comments {
+            let comment = self
+                .program
+                .chars()
+                .skip(self.start + 1)
+                .take(self.position - self.start - 1)
+                .collect();
+
+            self.push_token(TokenKind::Comment(comment));
+        }
     }
 
     fn parse_string(&mut self) -> Result<()> {
@@ -61,11 +97,8 @@ impl<'a> Lexer<'a> {
             match self.current() {
                 Some(character) => {
                     if character == '\n' {
-                        return Err(FlushError(
-                            self.file_path.clone(),
-                            self.line,
-                            "Illegal newline in a string".to_string(),
-                        ));
+                        return Err(FlushError::new(self.span(), "Illegal newline in a string")
+                            .with_help("strings cannot span several lines, use `+` to join them"));
                     }
 
                     string.push(character);
@@ -77,11 +110,8 @@ impl<'a> Lexer<'a> {
         }
 
         if self.current() != Some('"') {
-            return Err(FlushError(
-                self.file_path.clone(),
-                self.line,
-                "Unterminated string".to_string(),
-            ));
+            return Err(FlushError::new(self.span(), "Unterminated string")
+                .with_help("add a closing `\"` at the end of the string"));
         }
 
         self.position += 1;
@@ -107,10 +137,16 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        match raw_number.parse::<i32>() {
-            Ok(int) => self.push_token(TokenKind::Int(int)),
-            Err(_) => self.push_token(TokenKind::Float(raw_number.parse::<f64>().unwrap())),
-        }
+        let kind = if raw_number.contains('.') {
+            TokenKind::Float(raw_number.parse::<f64>().unwrap())
+        } else {
+            match raw_number.parse::<i64>() {
+                Ok(int) => TokenKind::Int(int),
+                Err(_) => TokenKind::BigInt(raw_number.parse::<BigInt>().unwrap()),
+            }
+        };
+
+        self.push_token(kind);
     }
 
     fn parse_identifier(&mut self) {
@@ -139,7 +175,11 @@ impl<'a> Lexer<'a> {
             "for" => TokenKind::For,
             "in" => TokenKind::In,
             "break" => TokenKind::Break,
+            "continue" => TokenKind::Continue,
             "load" => TokenKind::Load,
+            "and" => TokenKind::Op(Op::And),
+            "or" => TokenKind::Op(Op::Or),
+            "not" => TokenKind::Not,
             "true" => TokenKind::Boolean(true),
             "false" => TokenKind::Boolean(false),
             ident => TokenKind::Ident(ident.to_string()),
@@ -149,6 +189,8 @@ impl<'a> Lexer<'a> {
     }
 
     fn parse_token(&mut self) -> Result<()> {
+        self.start = self.position;
+
         if let Some(character) = self.advance() {
             match character {
                 '(' => self.push_token(TokenKind::LParen),
@@ -159,6 +201,12 @@ impl<'a> Lexer<'a> {
                 ']' => self.push_token(TokenKind::RBracket),
                 ',' => self.push_token(TokenKind::Comma),
                 ':' => self.push_token(TokenKind::Colon),
+                '.' if self.current() == Some('.')
+                    && self.program.chars().nth(self.position + 1) == Some('.') =>
+                {
+                    self.push_token(TokenKind::Ellipsis);
+                    self.position += 2;
+                }
                 '+' => self.push_token(TokenKind::Op(Op::Add)),
                 '-' => self.push_token(TokenKind::Op(Op::Sub)),
                 '*' => self.push_token(TokenKind::Op(Op::Mul)),
@@ -201,9 +249,14 @@ impl<'a> Lexer<'a> {
                 }
                 '"' => self.parse_string()?,
                 '#' => self.skip_comment(),
-                '\n' => self.line += 1,
+                '\n' => {
+                    self.line += 1;
+                    self.line_start = self.position;
+                }
                 _ if character.is_ascii_digit() => self.parse_number(),
-                '_' | _ if character.is_ascii_alphanumeric() => self.parse_identifier(),
+                _ if character == '_' || character.is_ascii_alphanumeric() => {
+                    self.parse_identifier()
+                }
                 _ => (),
             };
         }
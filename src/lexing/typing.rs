@@ -1,29 +1,37 @@
+use crate::error::Span;
+use num_bigint::BigInt;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum TokenKind {
-    LParen,         // (
-    RParen,         // )
-    LBrace,         // {
-    RBrace,         // }
-    LBracket,       // [
-    RBracket,       // ]
-    Comma,          // ,
-    Colon,          // :
-    If,             // if
-    Else,           // else
-    Def,            // def
-    Return,         // return
-    While,          // while
-    For,            // for
-    In,             // in
-    Break,          // break
-    Load,           // load
-    String(String), // "Hello, World!"
-    Int(i32),       // 42
-    Float(f64),     // 3.14
-    Boolean(bool),  // false
-    Ident(String),  // user
-    Op(Op),         // + - * / % < > == /= <= >=
-    Assign,         // =
+    LParen,          // (
+    RParen,          // )
+    LBrace,          // {
+    RBrace,          // }
+    LBracket,        // [
+    RBracket,        // ]
+    Comma,           // ,
+    Colon,           // :
+    Ellipsis,        // ...
+    If,              // if
+    Else,            // else
+    Def,             // def
+    Return,          // return
+    While,           // while
+    For,             // for
+    In,              // in
+    Break,           // break
+    Continue,        // continue
+    Load,            // load
+    Not,             // not
+    String(String),  // "Hello, World!"
+    Int(i64),        // 42
+    BigInt(BigInt),  // 36893488147419103232
+    Float(f64),      // 3.14
+    Boolean(bool),   // false
+    Ident(String),   // user
+    Op(Op),          // + - * / % < > == /= <= >= and or
+    Assign,          // =
+    Comment(String), // # ...
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -41,10 +49,12 @@ pub enum Op {
     Ne,       // /=
     Le,       // <=
     Ge,       // >=
+    And,      // and
+    Or,       // or
 }
 
 #[derive(Clone, Debug)]
 pub struct Token {
-    pub line: usize,
+    pub span: Span,
     pub kind: TokenKind,
 }
@@ -0,0 +1,23 @@
+#![allow(clippy::vec_box, clippy::boxed_local)]
+
+mod checking;
+#[doc(hidden)]
+pub mod cli;
+mod compiling;
+mod engine;
+mod error;
+mod flush;
+mod formatting;
+mod interpreting;
+mod lexing;
+mod lsp;
+mod parsing;
+mod repl;
+mod test_runner;
+mod value;
+
+pub use engine::Engine;
+pub use error::{Diagnostic, FlushError, Frame, Level, Result, SourceFile, Span};
+pub use interpreting::host::{check_arity, get_arg, Ctx};
+pub use num_bigint::BigInt;
+pub use value::{Kind, Value};
@@ -0,0 +1,590 @@
+use crate::checking::checker::{analyze, Analysis, Definition};
+use crate::error::{FlushError, Level, Span};
+use crate::flush;
+use crate::formatting::formatter;
+use crate::interpreting::builtins;
+use crate::lexing::lexer::Lexer;
+use crate::lexing::typing::TokenKind;
+use crate::parsing::typing::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{stdin, stdout, BufRead, Write};
+use std::path::PathBuf;
+
+const KEYWORDS: [&str; 15] = [
+    "if", "else", "def", "return", "while", "for", "in", "break", "continue", "load", "not", "and",
+    "or", "true", "false",
+];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+const SYMBOL_FUNCTION: i64 = 12;
+const SYMBOL_VARIABLE: i64 = 13;
+const COMPLETION_FUNCTION: i64 = 3;
+const COMPLETION_VARIABLE: i64 = 6;
+const COMPLETION_KEYWORD: i64 = 14;
+
+type Response = std::result::Result<Value, (i64, String)>;
+
+pub struct Server<W: Write> {
+    writer: W,
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+fn read_message(reader: &mut impl BufRead) -> Option<Vec<u8>> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+
+        if reader.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+
+        let header = header.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let mut content = vec![0; length?];
+    reader.read_exact(&mut content).ok()?;
+
+    Some(content)
+}
+
+fn uri_to_path(uri: &str) -> PathBuf {
+    let path = match uri.strip_prefix("file://") {
+        Some(path) => path,
+        None => return PathBuf::from(uri),
+    };
+    let bytes = path.as_bytes();
+    let mut decoded = vec![];
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escaped = match bytes.get(index + 1..index + 3) {
+            Some(hex) if bytes[index] == b'%' => std::str::from_utf8(hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    PathBuf::from(String::from_utf8_lossy(&decoded).to_string())
+}
+
+fn path_to_uri(path: &std::path::Path) -> String {
+    let mut uri = "file://".to_string();
+
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    uri
+}
+
+fn utf16_length(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+fn position(span: &Span, line: usize, column: usize) -> Value {
+    let source = span.file.line(line).unwrap_or_default();
+    let prefix: String = source.chars().take(column.saturating_sub(1)).collect();
+
+    json!({ "line": line.saturating_sub(1), "character": utf16_length(&prefix) })
+}
+
+fn range(span: &Span) -> Value {
+    if span.is_unknown() {
+        return json!({
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": 0 },
+        });
+    }
+
+    let (end_line, end_column) = if span.end_line >= span.line && span.end_column > 0 {
+        (span.end_line, span.end_column)
+    } else {
+        (span.line, span.column + span.length)
+    };
+
+    json!({
+        "start": position(span, span.line, span.column),
+        "end": position(span, end_line, end_column),
+    })
+}
+
+fn location(span: &Span) -> Value {
+    json!({ "uri": path_to_uri(&span.file.path), "range": range(span) })
+}
+
+fn cursor(text: &str, position: &Value) -> Option<(usize, usize)> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+    let source = text.lines().nth(line).unwrap_or_default();
+    let mut units = 0;
+    let mut column = 1;
+
+    for char in source.chars() {
+        if units >= character {
+            break;
+        }
+
+        units += char.len_utf16();
+        column += 1;
+    }
+
+    Some((line + 1, column))
+}
+
+fn word_at(text: &str, (line, column): (usize, usize)) -> Option<String> {
+    let source: Vec<char> = text.lines().nth(line - 1)?.chars().collect();
+    let is_word = |char: &char| *char == '_' || char.is_ascii_alphanumeric();
+    let mut start = (column - 1).min(source.len());
+    let mut end = start;
+
+    while start > 0 && is_word(&source[start - 1]) {
+        start -= 1;
+    }
+
+    while end < source.len() && is_word(&source[end]) {
+        end += 1;
+    }
+
+    if start == end {
+        return None;
+    }
+
+    Some(source[start..end].iter().collect())
+}
+
+fn span_text(span: &Span) -> String {
+    let source = span.file.line(span.line).unwrap_or_default();
+    let length = if span.end_line > span.line {
+        usize::MAX
+    } else {
+        span.length
+    };
+
+    source
+        .chars()
+        .skip(span.column.saturating_sub(1))
+        .take(length)
+        .collect()
+}
+
+fn signature(definition: &Definition) -> String {
+    match &definition.params {
+        Some(params) => {
+            let params: Vec<String> = params.iter().map(|param| span_text(&param.span)).collect();
+
+            format!("def {}({})", definition.name, params.join(", "))
+        }
+        None => span_text(&definition.span)
+            .trim_end_matches('{')
+            .trim()
+            .to_string(),
+    }
+}
+
+fn diagnostic(error: &FlushError, file_path: &PathBuf) -> Value {
+    let mut message = error.message.clone();
+    let span = if error.span.file.path == *file_path {
+        error.span.clone()
+    } else {
+        if !error.span.is_unknown() {
+            message = format!(
+                "{} ({}:{}:{})",
+                message,
+                error.span.file.path.display(),
+                error.span.line,
+                error.span.column
+            );
+        }
+
+        Span::default()
+    };
+
+    if let Some(help) = &error.help {
+        message = format!("{}\nhelp: {}", message, help);
+    }
+
+    let related: Vec<Value> = error
+        .labels
+        .iter()
+        .filter(|(span, _)| !span.is_unknown())
+        .map(|(span, label)| json!({ "location": location(span), "message": label }))
+        .collect();
+
+    json!({
+        "range": range(&span),
+        "severity": match error.level {
+            Level::Error => 1,
+            Level::Warning => 2,
+        },
+        "code": error.code,
+        "source": "flush",
+        "message": message,
+        "relatedInformation": related,
+    })
+}
+
+fn symbols(statements: &[Statement]) -> Vec<Value> {
+    let mut symbols = vec![];
+
+    for statement in statements {
+        let (id, params, body) = match &statement.kind {
+            StatementKind::FuncDef(id, params, body, _) => (id, Some(params), body.to_vec()),
+            StatementKind::VarDef(id, expr, _) => match &expr.kind {
+                ExprKind::Lambda(params, body) => (id, Some(params), body.to_vec()),
+                _ => (id, None, vec![]),
+            },
+            StatementKind::If(_, if_body, else_body) => {
+                let body: Vec<Statement> = if_body
+                    .iter()
+                    .chain(else_body)
+                    .map(|statement| *statement.clone())
+                    .collect();
+
+                symbols.extend(self::symbols(&body));
+                continue;
+            }
+            _ => continue,
+        };
+        let definition = Definition {
+            name: id.clone(),
+            span: statement.span.clone(),
+            params: params.cloned(),
+        };
+
+        symbols.push(json!({
+            "name": id,
+            "detail": signature(&definition),
+            "kind": if params.is_some() { SYMBOL_FUNCTION } else { SYMBOL_VARIABLE },
+            "range": range(&statement.span),
+            "selectionRange": range(&statement.span),
+            "children": self::symbols(&body),
+        }));
+    }
+
+    symbols
+}
+
+impl<W: Write> Server<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    fn send(&mut self, message: Value) {
+        let content = message.to_string();
+
+        let _ = write!(
+            self.writer,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        );
+        let _ = self.writer.flush();
+    }
+
+    fn respond(&mut self, id: Value, response: Response) {
+        let message = match response {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+
+        self.send(message);
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn document(&self, params: &Value) -> std::result::Result<(String, String), (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        match self.documents.get(uri) {
+            Some(text) => Ok((uri.to_string(), text.clone())),
+            None => Err((INVALID_REQUEST, format!("`{}` is not opened", uri))),
+        }
+    }
+
+    fn analysis(uri: &str, text: &str) -> Option<Analysis> {
+        analyze(text, uri_to_path(uri)).ok()
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) {
+        let file_path = uri_to_path(uri);
+        let diagnostics: Vec<Value> = match self.documents.get(uri) {
+            Some(text) => match analyze(text, file_path.clone()) {
+                Ok(analysis) => analysis
+                    .diagnostics
+                    .iter()
+                    .map(|error| diagnostic(error, &file_path))
+                    .collect(),
+                Err(error) => vec![diagnostic(&error, &file_path)],
+            },
+            None => vec![],
+        };
+
+        self.notify(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        );
+    }
+
+    fn definition_at(
+        &self,
+        params: &Value,
+    ) -> std::result::Result<Option<Definition>, (i64, String)> {
+        let (uri, text) = self.document(params)?;
+        let (line, column) = match cursor(&text, &params["position"]) {
+            Some(cursor) => cursor,
+            None => return Ok(None),
+        };
+        let analysis = match Self::analysis(&uri, &text) {
+            Some(analysis) => analysis,
+            None => return Ok(None),
+        };
+
+        Ok(analysis
+            .references
+            .into_iter()
+            .find(|(span, _)| {
+                span.line == line && span.column <= column && column <= span.column + span.length
+            })
+            .map(|(_, definition)| definition))
+    }
+
+    fn initialize(&mut self) -> Response {
+        Ok(json!({
+            "capabilities": {
+                "textDocumentSync": 1,
+                "definitionProvider": true,
+                "hoverProvider": true,
+                "completionProvider": {},
+                "documentSymbolProvider": true,
+                "documentFormattingProvider": true,
+            },
+            "serverInfo": { "name": "flush", "version": env!("CARGO_PKG_VERSION") },
+        }))
+    }
+
+    fn definition(&mut self, params: &Value) -> Response {
+        Ok(match self.definition_at(params)? {
+            Some(definition) => location(&definition.span),
+            None => Value::Null,
+        })
+    }
+
+    fn hover(&mut self, params: &Value) -> Response {
+        let contents = match self.definition_at(params)? {
+            Some(definition) => format!("```flush\n{}\n```", signature(&definition)),
+            None => {
+                let (_, text) = self.document(params)?;
+                let word =
+                    cursor(&text, &params["position"]).and_then(|cursor| word_at(&text, cursor));
+
+                match word {
+                    Some(word) if builtins::names().contains(&word.as_str()) => {
+                        format!("```flush\n{}(...)\n```\nbuiltin function", word)
+                    }
+                    _ => return Ok(Value::Null),
+                }
+            }
+        };
+
+        Ok(json!({ "contents": { "kind": "markdown", "value": contents } }))
+    }
+
+    fn completion(&mut self, params: &Value) -> Response {
+        let (uri, text) = self.document(params)?;
+        let mut items: Vec<Value> = vec![];
+        let mut seen: Vec<String> = vec![];
+
+        let mut add = |label: &str, kind: i64, detail: String| {
+            if !seen.iter().any(|name| name == label) {
+                seen.push(label.to_string());
+                items.push(json!({ "label": label, "kind": kind, "detail": detail }));
+            }
+        };
+
+        match Self::analysis(&uri, &text) {
+            Some(analysis) => {
+                for definition in &analysis.definitions {
+                    let kind = if definition.params.is_some() {
+                        COMPLETION_FUNCTION
+                    } else {
+                        COMPLETION_VARIABLE
+                    };
+
+                    add(&definition.name, kind, signature(definition));
+                }
+            }
+            None => {
+                if let Ok(tokens) = Lexer::new(&text, uri_to_path(&uri)).tokenize() {
+                    for token in tokens {
+                        if let TokenKind::Ident(id) = &token.kind {
+                            add(id, COMPLETION_VARIABLE, String::new());
+                        }
+                    }
+                }
+            }
+        }
+
+        for builtin in builtins::names() {
+            add(builtin, COMPLETION_FUNCTION, "builtin function".to_string());
+        }
+
+        for keyword in KEYWORDS {
+            add(keyword, COMPLETION_KEYWORD, "keyword".to_string());
+        }
+
+        Ok(Value::from(items))
+    }
+
+    fn document_symbol(&mut self, params: &Value) -> Response {
+        let (uri, text) = self.document(params)?;
+
+        Ok(match flush::parse(&text, uri_to_path(&uri)) {
+            Ok(statements) => Value::from(symbols(&statements)),
+            Err(_) => Value::Null,
+        })
+    }
+
+    fn formatting(&mut self, params: &Value) -> Response {
+        let (uri, text) = self.document(params)?;
+        let formatted = match formatter::format(&text, uri_to_path(&uri)) {
+            Ok(formatted) => formatted,
+            Err(_) => return Ok(Value::Null),
+        };
+
+        if formatted == text {
+            return Ok(json!([]));
+        }
+
+        let lines: Vec<&str> = text.split('\n').collect();
+        let end = json!({
+            "line": lines.len() - 1,
+            "character": utf16_length(lines[lines.len() - 1]),
+        });
+
+        Ok(json!([{
+            "range": { "start": { "line": 0, "character": 0 }, "end": end },
+            "newText": formatted,
+        }]))
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Response {
+        match method {
+            "initialize" => self.initialize(),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/documentSymbol" => self.document_symbol(params),
+            "textDocument/formatting" => self.formatting(params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method `{}`", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+
+                self.documents.insert(uri.clone(), text.to_string());
+                self.publish_diagnostics(&uri);
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+
+                if let Some(text) = changes.and_then(|changes| changes.last()?["text"].as_str()) {
+                    self.documents.insert(uri.clone(), text.to_string());
+                    self.publish_diagnostics(&uri);
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.publish_diagnostics(&uri);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn serve(&mut self, reader: &mut impl BufRead) -> i32 {
+        while let Some(content) = read_message(reader) {
+            let message: Value = match serde_json::from_slice(&content) {
+                Ok(message) => message,
+                Err(e) => {
+                    self.respond(Value::Null, Err((PARSE_ERROR, e.to_string())));
+                    continue;
+                }
+            };
+            let method = match message["method"].as_str() {
+                Some(method) => method,
+                None => continue,
+            };
+
+            if method == "exit" {
+                return if self.shutdown { 0 } else { 1 };
+            }
+
+            match message.get("id") {
+                Some(id) => {
+                    let response = self.request(method, &message["params"]);
+                    self.respond(id.clone(), response);
+                }
+                None => self.notification(method, &message["params"]),
+            }
+        }
+
+        1
+    }
+}
+
+pub fn start() -> i32 {
+    Server::new(stdout().lock()).serve(&mut stdin().lock())
+}
@@ -1,21 +1,25 @@
-mod error;
-mod flush;
-mod interpreting;
-mod lexing;
-mod parsing;
-
-use ansi_term::Color::Red;
 use std::env::args;
+use std::io::{stdout, Write};
+use std::process::exit;
+use std::thread;
+
+const STACK_SIZE: usize = 512 * 1024 * 1024;
 
 fn main() {
-    let raw_file_path = match args().nth(1) {
-        Some(path) => path,
-        None => return eprintln!("{}: Usage: flush <file_path>", Red.paint("[error]")),
-    };
+    let interpreter = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .unwrap();
+
+    let code = interpreter.join().unwrap_or(101);
+
+    let _ = stdout().flush();
+
+    exit(code);
+}
 
-    let mut cache = vec![];
+fn run() -> i32 {
+    let args: Vec<String> = args().skip(1).collect();
 
-    if let Err(e) = flush::run(&raw_file_path, &mut cache) {
-        eprintln!("{}", e);
-    }
+    flush::cli::start(&args)
 }
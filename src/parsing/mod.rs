@@ -1,2 +1,4 @@
 pub mod parser;
+#[cfg(test)]
+mod tests;
 pub mod typing;
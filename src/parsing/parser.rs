@@ -1,22 +1,18 @@
 use super::typing::*;
-use crate::error::{FlushError, Result};
+use crate::error::{FlushError, Result, Span};
 use crate::lexing::typing::*;
 use std::collections::HashMap;
-use std::path::PathBuf;
 
 pub struct Parser<'a> {
     tokens: &'a Vec<Token>,
-    file_path: PathBuf,
     statements: Vec<Statement>,
     position: usize,
 }
 
-#[allow(unreachable_patterns)]
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &'a Vec<Token>, file_path: PathBuf) -> Self {
+    pub fn new(tokens: &'a Vec<Token>) -> Self {
         Self {
             tokens,
-            file_path,
             statements: vec![],
             position: 0,
         }
@@ -30,32 +26,34 @@ impl<'a> Parser<'a> {
         self.tokens[self.position].clone()
     }
 
+    fn check(&self, kind: &TokenKind) -> bool {
+        !self.is_at_end() && &self.tokens[self.position].kind == kind
+    }
+
     fn advance(&mut self) -> Option<Token> {
         if self.is_at_end() {
             return None;
         }
 
         self.position += 1;
-        Some(self.previous().clone())
+        Some(self.previous())
     }
 
     fn expect(&mut self, expected: TokenKind) -> Result<Token> {
         if self.is_at_end() {
-            return Err(FlushError(
-                self.file_path.clone(),
-                self.previous().line,
+            return Err(FlushError::new(
+                self.previous().span,
                 format!("Expected {:?}, found nothing", expected),
             ));
         }
 
         self.position += 1;
 
-        let next = self.previous().clone();
+        let next = self.previous();
 
         if next.kind != expected {
-            return Err(FlushError(
-                self.file_path.clone(),
-                next.line,
+            return Err(FlushError::new(
+                next.span,
                 format!("Expected {:?}, found {:?}", expected, next.kind),
             ));
         }
@@ -64,112 +62,125 @@ impl<'a> Parser<'a> {
     }
 
     fn is_at_end(&self) -> bool {
-        self.tokens.len() > 0 && self.position == self.tokens.len()
+        self.position >= self.tokens.len()
+    }
+
+    fn span_from(&self, start: usize) -> Span {
+        self.tokens[start].span.to(&self.previous().span)
     }
 
     fn parse_statement(&mut self) -> Result<Statement> {
-        Ok(match self.advance().unwrap().kind {
+        let start = self.position;
+
+        let kind = match self.advance().unwrap().kind {
             TokenKind::If => self.parse_control_flow()?,
             TokenKind::Def => self.parse_def()?,
-            TokenKind::Return => Statement::Return(self.parse_expr()?),
+            TokenKind::Return => StatementKind::Return(self.parse_expr()?),
             TokenKind::While => self.parse_while()?,
             TokenKind::For => self.parse_for()?,
-            TokenKind::Break => Statement::Break,
+            TokenKind::Break => StatementKind::Break,
+            TokenKind::Continue => StatementKind::Continue,
             TokenKind::Load => self.parse_load()?,
-            unknow => {
+            _ => {
                 self.position -= 1;
 
-                match self.parse_expr() {
-                    Ok(Expr::Var(var)) => {
-                        if self.is_at_end() {
-                            Statement::Expr(Expr::Var(var))
-                        } else {
-                            if self.advance().unwrap().kind == TokenKind::Assign {
-                                self.parse_var_set(var)?
-                            } else {
-                                self.position -= 1;
-                                Statement::Expr(Expr::Var(var))
-                            }
-                        }
-                    }
-                    Ok(Expr::Index(expr, index)) => {
-                        if self.is_at_end() {
-                            Statement::Expr(Expr::Index(expr, index))
-                        } else {
-                            if self.advance().unwrap().kind == TokenKind::Assign {
-                                self.parse_index_set(*expr, *index)?
-                            } else {
-                                self.position -= 1;
-                                Statement::Expr(Expr::Index(expr, index))
-                            }
+                let expr = self.parse_expr()?;
+
+                if !self.check(&TokenKind::Assign) {
+                    StatementKind::Expr(expr)
+                } else {
+                    self.position += 1;
+
+                    match expr.kind {
+                        ExprKind::Var(var, _) => self.parse_var_set(var)?,
+                        ExprKind::Index(expr, index) => self.parse_index_set(*expr, *index)?,
+                        _ => {
+                            return Err(FlushError::new(
+                                expr.span,
+                                "Cannot assign to this expression",
+                            )
+                            .with_help(
+                                "only variables and indexes like `list[0]` can be assigned",
+                            ))
                         }
                     }
-                    Ok(expr) => Statement::Expr(expr),
-                    x => {
-                        println!("{:?}", x);
-                        return Err(FlushError(
-                            self.file_path.clone(),
-                            self.previous().line,
-                            format!("Unknow statement {:?}", unknow),
-                        ));
-                    }
                 }
             }
+        };
+
+        Ok(Statement {
+            kind,
+            span: self.span_from(start),
         })
     }
 
-    fn parse_control_flow(&mut self) -> Result<Statement> {
+    fn parse_block(&mut self) -> Result<Vec<Box<Statement>>> {
+        let open = self.expect(TokenKind::LBrace)?;
+
+        let mut body: Vec<Box<Statement>> = vec![];
+
+        while !self.is_at_end() && !self.check(&TokenKind::RBrace) {
+            body.push(Box::new(self.parse_statement()?));
+        }
+
+        if self.is_at_end() {
+            return Err(
+                FlushError::new(self.previous().span, "Expected RBrace, found nothing")
+                    .with_label(open.span, "this block is never closed"),
+            );
+        }
+
+        self.expect(TokenKind::RBrace)?;
+
+        Ok(body)
+    }
+
+    fn parse_control_flow(&mut self) -> Result<StatementKind> {
         self.expect(TokenKind::LParen)?;
 
         let condition = self.parse_expr()?;
 
         self.expect(TokenKind::RParen)?;
-        self.expect(TokenKind::LBrace)?;
 
-        let mut if_body: Vec<Box<Statement>> = vec![];
+        let if_body = self.parse_block()?;
         let mut else_body: Vec<Box<Statement>> = vec![];
 
-        while !self.is_at_end() && self.current().kind != TokenKind::RBrace {
-            if_body.push(Box::new(self.parse_statement()?));
-        }
+        if self.check(&TokenKind::Else) {
+            self.position += 1;
 
-        self.expect(TokenKind::RBrace)?;
+            if self.check(&TokenKind::If) {
+                let start = self.position;
 
-        if self.is_at_end() {
-            return Ok(Statement::If(condition, if_body, vec![]));
-        }
+                self.position += 1;
 
-        if self.advance().unwrap().kind == TokenKind::Else {
-            self.expect(TokenKind::LBrace)?;
+                let kind = self.parse_control_flow()?;
 
-            while !self.is_at_end() && self.current().kind != TokenKind::RBrace {
-                else_body.push(Box::new(self.parse_statement()?));
+                else_body.push(Box::new(Statement {
+                    kind,
+                    span: self.span_from(start),
+                }));
+            } else {
+                else_body = self.parse_block()?;
             }
-
-            self.expect(TokenKind::RBrace)?;
-        } else {
-            self.position -= 1;
         }
 
-        Ok(Statement::If(condition, if_body, else_body))
+        Ok(StatementKind::If(condition, if_body, else_body))
     }
 
-    fn parse_def(&mut self) -> Result<Statement> {
+    fn parse_def(&mut self) -> Result<StatementKind> {
         let id = match self.advance() {
             Some(token) => match token.kind {
                 TokenKind::Ident(id) => id,
                 kind => {
-                    return Err(FlushError(
-                        self.file_path.clone(),
-                        token.line,
+                    return Err(FlushError::new(
+                        token.span,
                         format!("Expected Identifier, found {:?}", kind),
                     ))
                 }
             },
             _ => {
-                return Err(FlushError(
-                    self.file_path.clone(),
-                    self.previous().line,
+                return Err(FlushError::new(
+                    self.previous().span,
                     "Expected Identifier, found nothing".to_string(),
                 ))
             }
@@ -178,109 +189,156 @@ impl<'a> Parser<'a> {
         let token = match self.advance() {
             Some(token) => token,
             None => {
-                return Err(FlushError(
-                    self.file_path.clone(),
-                    self.previous().line,
+                return Err(FlushError::new(
+                    self.previous().span,
                     "Expected Equal, found nothing".to_string(),
                 ))
             }
         };
 
         Ok(match token.kind {
-            TokenKind::Assign => Statement::VarDef(id, self.parse_expr()?),
+            TokenKind::Assign => {
+                StatementKind::VarDef(id, self.parse_expr()?, Resolution::default())
+            }
             TokenKind::LParen => self.parse_func_def(id)?,
             unexpected => {
-                return Err(FlushError(
-                    self.file_path.clone(),
-                    token.line,
+                return Err(FlushError::new(
+                    token.span,
                     format!("Unexpected token {:?}", unexpected),
                 ))
             }
         })
     }
 
-    fn parse_func_def(&mut self, id: String) -> Result<Statement> {
-        let mut args: Vec<String> = vec![];
+    fn parse_func_def(&mut self, id: String) -> Result<StatementKind> {
+        let (args, body) = self.parse_function()?;
 
-        while !self.is_at_end() && self.current().kind != TokenKind::RParen {
-            match self.advance().unwrap().kind {
-                TokenKind::Ident(id) => args.push(id),
-                unexpected => {
-                    return Err(FlushError(
-                        self.file_path.clone(),
-                        self.previous().line,
-                        format!("Unexpected token {:?}", unexpected),
-                    ))
-                }
-            };
+        Ok(StatementKind::FuncDef(
+            id,
+            args,
+            body.into(),
+            Resolution::default(),
+        ))
+    }
 
-            if self.current().kind == TokenKind::RParen {
-                break;
-            }
+    fn parse_function(&mut self) -> Result<(Vec<Param>, Vec<Statement>)> {
+        let mut args: Vec<Param> = vec![];
 
-            self.expect(TokenKind::Comma)?;
-        }
+        while !self.is_at_end() && !self.check(&TokenKind::RParen) {
+            let start = self.position;
+            let kind = self.parse_param()?;
+            let span = self.span_from(start);
+            let param = Param {
+                kind,
+                span: span.clone(),
+            };
 
-        self.expect(TokenKind::RParen)?;
-        self.expect(TokenKind::LBrace)?;
+            if let Some(ParamKind::Rest(rest)) = args.last().map(|arg| &arg.kind) {
+                return Err(FlushError::new(
+                    span,
+                    format!("The rest parameter `{}` must be the last one", rest),
+                ));
+            }
 
-        let mut body: Vec<Statement> = vec![];
+            if args.iter().any(|arg| arg.name() == param.name()) {
+                return Err(FlushError::new(
+                    span,
+                    format!("Duplicate parameter `{}`", param.name()),
+                ));
+            }
 
-        while !self.is_at_end() {
-            let current = match self.advance() {
-                Some(token) => token,
-                None => {
-                    return Err(FlushError(
-                        self.file_path.clone(),
-                        self.previous().line,
-                        "Unfinished function body".to_string(),
-                    ))
+            if let ParamKind::Required(id) = &param.kind {
+                if args
+                    .iter()
+                    .any(|arg| matches!(arg.kind, ParamKind::Optional(_, _)))
+                {
+                    return Err(FlushError::new(
+                        span,
+                        format!(
+                            "The parameter `{}` must have a default value, like the ones before it",
+                            id
+                        ),
+                    ));
                 }
-            };
+            }
 
-            self.position -= 1;
+            args.push(param);
 
-            if current.kind == TokenKind::RBrace {
+            if self.check(&TokenKind::RParen) {
                 break;
             }
 
-            body.push(self.parse_statement()?);
+            self.expect(TokenKind::Comma)?;
         }
 
-        self.expect(TokenKind::RBrace)?;
+        self.expect(TokenKind::RParen)?;
 
-        Ok(Statement::FuncDef(id, args, body))
+        let body = self
+            .parse_block()?
+            .into_iter()
+            .map(|statement| *statement)
+            .collect();
+
+        Ok((args, body))
     }
 
-    fn parse_while(&mut self) -> Result<Statement> {
+    fn parse_param(&mut self) -> Result<ParamKind> {
+        let token = self.advance().unwrap();
+
+        Ok(match token.kind {
+            TokenKind::Ellipsis => match self.advance() {
+                Some(Token {
+                    kind: TokenKind::Ident(id),
+                    ..
+                }) => ParamKind::Rest(id),
+                unexpected => {
+                    return Err(FlushError::new(
+                        self.previous().span,
+                        format!("Expected Identifier, found {:?}", unexpected),
+                    ))
+                }
+            },
+            TokenKind::Ident(id) => {
+                if self.check(&TokenKind::Assign) {
+                    self.position += 1;
+                    ParamKind::Optional(id, self.parse_expr()?)
+                } else {
+                    ParamKind::Required(id)
+                }
+            }
+            unexpected => {
+                return Err(FlushError::new(
+                    token.span,
+                    format!("Unexpected token {:?}", unexpected),
+                ))
+            }
+        })
+    }
+
+    fn parse_while(&mut self) -> Result<StatementKind> {
         self.expect(TokenKind::LParen)?;
 
         let condition = self.parse_expr()?;
 
         self.expect(TokenKind::RParen)?;
-        self.expect(TokenKind::LBrace)?;
 
-        let mut body: Vec<Box<Statement>> = vec![];
+        let body = self.parse_block()?;
 
-        while !self.is_at_end() && self.current().kind != TokenKind::RBrace {
-            body.push(Box::new(self.parse_statement()?));
-        }
-
-        self.expect(TokenKind::RBrace)?;
-
-        Ok(Statement::While(condition, body))
+        Ok(StatementKind::While(condition, body))
     }
 
-    fn parse_for(&mut self) -> Result<Statement> {
+    fn parse_for(&mut self) -> Result<StatementKind> {
         self.expect(TokenKind::LParen)?;
 
-        let id = match self.parse_expr()? {
-            Expr::Var(id) => id,
+        let (id, id_span) = match self.parse_expr()? {
+            Expr {
+                kind: ExprKind::Var(id, _),
+                span,
+            } => (id, span),
             unexpected => {
-                return Err(FlushError(
-                    self.file_path.clone(),
-                    self.previous().line,
-                    format!("Expected Identifier, found {:?}", unexpected),
+                return Err(FlushError::new(
+                    unexpected.span,
+                    format!("Expected Identifier, found {:?}", unexpected.kind),
                 ))
             }
         };
@@ -290,144 +348,137 @@ impl<'a> Parser<'a> {
         let iterator = self.parse_expr()?;
 
         self.expect(TokenKind::RParen)?;
-        self.expect(TokenKind::LBrace)?;
 
-        let mut body: Vec<Box<Statement>> = vec![];
+        let body = self.parse_block()?;
 
-        while !self.is_at_end() && self.current().kind != TokenKind::RBrace {
-            body.push(Box::new(self.parse_statement()?));
-        }
-
-        self.expect(TokenKind::RBrace)?;
-
-        Ok(Statement::For(id, iterator, body))
+        Ok(StatementKind::For(
+            id,
+            id_span,
+            iterator,
+            body,
+            Resolution::default(),
+        ))
     }
 
-    fn parse_load(&mut self) -> Result<Statement> {
+    fn parse_load(&mut self) -> Result<StatementKind> {
         let raw_file_path = match self.parse_expr()? {
-            Expr::String(path) => path,
+            Expr {
+                kind: ExprKind::String(path),
+                ..
+            } => path,
             unexpected => {
-                return Err(FlushError(
-                    self.file_path.clone(),
-                    self.previous().line,
-                    format!("Expected String, found {:?}", unexpected),
+                return Err(FlushError::new(
+                    unexpected.span,
+                    format!("Expected String, found {:?}", unexpected.kind),
                 ))
             }
         };
 
-        Ok(Statement::Load(raw_file_path))
+        Ok(StatementKind::Load(raw_file_path))
     }
 
     fn parse_expr(&mut self) -> Result<Expr> {
+        self.parse_bin_op(0)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let start = self.position;
+
         let next = match self.advance() {
             Some(token) => token,
             unexpected => {
-                return Err(FlushError(
-                    self.file_path.clone(),
-                    self.previous().line,
+                return Err(FlushError::new(
+                    self.previous().span,
                     format!("Expected Expression, found {:?}", unexpected),
                 ))
             }
         };
 
-        let expr = match next.kind {
-            TokenKind::String(string) => {
-                if self.is_at_end() {
-                    return Ok(Expr::String(string));
-                }
+        let kind = match next.kind {
+            TokenKind::String(string) => ExprKind::String(string),
+            TokenKind::Int(int) => ExprKind::Int(int),
+            TokenKind::BigInt(int) => ExprKind::BigInt(int),
+            TokenKind::Float(float) => ExprKind::Float(float),
+            TokenKind::Boolean(boolean) => ExprKind::Boolean(boolean),
+            TokenKind::Ident(id) => ExprKind::Var(id, Resolution::default()),
+            TokenKind::LBracket => self.parse_list()?,
+            TokenKind::LBrace => self.parse_dict()?,
+            TokenKind::Def => {
+                self.expect(TokenKind::LParen)?;
 
-                match self.advance().unwrap().kind {
-                    TokenKind::LBracket => self.parse_index(Expr::String(string))?,
-                    _ => {
-                        self.position -= 1;
-                        Expr::String(string)
-                    }
-                }
-            }
-            TokenKind::Int(int) => Expr::Int(int),
-            TokenKind::Float(float) => Expr::Float(float),
-            TokenKind::Boolean(boolean) => Expr::Boolean(boolean),
-            TokenKind::Ident(id) => {
-                if self.is_at_end() {
-                    return Ok(Expr::Var(id));
-                }
+                let (args, body) = self.parse_function()?;
 
-                match self.advance().unwrap().kind {
-                    TokenKind::LParen => self.parse_func_call(id)?,
-                    TokenKind::LBracket => self.parse_index(Expr::Var(id))?,
-                    _ => {
-                        self.position -= 1;
-                        Expr::Var(id)
-                    }
-                }
+                ExprKind::Lambda(args, body.into())
             }
-            TokenKind::LBracket => self.parse_list()?,
-            TokenKind::LBrace => self.parse_dict()?,
-            TokenKind::Op(op) => {
-                if self.is_at_end() {
-                    return Err(FlushError(
-                        self.file_path.clone(),
-                        self.previous().line,
-                        "Expected Number, found nothing".to_string(),
-                    ));
-                }
+            TokenKind::LParen => {
+                let expr = self.parse_expr()?;
 
-                let expr = self.parse_number()?;
+                self.expect(TokenKind::RParen)?;
 
-                match expr {
-                    Expr::Int(int) => {
-                        if Op::Sub == op {
-                            return Ok(Expr::Int(-int));
-                        }
-                        return Err(FlushError(
-                            self.file_path.clone(),
-                            self.previous().line,
-                            format!("Expected Expression, found {:?}", op),
-                        ));
-                    }
-                    Expr::Float(float) => {
-                        if Op::Sub == op {
-                            return Ok(Expr::Float(-float));
-                        }
+                expr.kind
+            }
+            TokenKind::Not => {
+                let expr = self.parse_bin_op(BinOp::Eq.precedence().0)?;
 
-                        return Err(FlushError(
-                            self.file_path.clone(),
-                            self.previous().line,
-                            format!("Expected Expression, found {:?}", op),
-                        ));
-                    }
-                    _ => unreachable!(),
-                }
+                return Ok(Expr {
+                    kind: ExprKind::Unary(UnaryOp::Not, Box::new(expr)),
+                    span: self.span_from(start),
+                });
+            }
+            TokenKind::Op(Op::Sub) => {
+                let expr = self.parse_bin_op(BinOp::Pow.precedence().0)?;
+
+                return Ok(Expr {
+                    kind: ExprKind::Unary(UnaryOp::Neg, Box::new(expr)),
+                    span: self.span_from(start),
+                });
             }
             unexpected => {
-                return Err(FlushError(
-                    self.file_path.clone(),
-                    next.line,
+                return Err(FlushError::new(
+                    next.span,
                     format!("Expected Expression, found {:?}", unexpected),
                 ))
             }
         };
 
-        if self.is_at_end() {
-            return Ok(expr);
+        let expr = Expr {
+            kind,
+            span: self.span_from(start),
+        };
+
+        self.parse_postfix(expr, start)
+    }
+
+    fn parse_postfix(&mut self, mut expr: Expr, start: usize) -> Result<Expr> {
+        while !self.is_at_end() {
+            let kind = match self.current().kind {
+                TokenKind::LBracket => {
+                    self.position += 1;
+                    self.parse_index(expr)?
+                }
+                TokenKind::LParen => {
+                    self.position += 1;
+                    self.parse_func_call(expr)?
+                }
+                _ => break,
+            };
+
+            expr = Expr {
+                kind,
+                span: self.span_from(start),
+            };
         }
 
-        Ok(match self.advance().unwrap().kind {
-            TokenKind::Op(op) => self.parse_bin_op(expr, op)?,
-            _ => {
-                self.position -= 1;
-                return Ok(expr);
-            }
-        })
+        Ok(expr)
     }
 
-    fn parse_list(&mut self) -> Result<Expr> {
+    fn parse_list(&mut self) -> Result<ExprKind> {
         let mut expressions: Vec<Box<Expr>> = vec![];
 
-        while !self.is_at_end() && self.current().kind != TokenKind::RBracket {
+        while !self.is_at_end() && !self.check(&TokenKind::RBracket) {
             expressions.push(Box::new(self.parse_expr()?));
 
-            if self.current().kind == TokenKind::RBracket {
+            if self.check(&TokenKind::RBracket) {
                 break;
             }
 
@@ -436,36 +487,30 @@ impl<'a> Parser<'a> {
 
         self.expect(TokenKind::RBracket)?;
 
-        if !self.is_at_end() {
-            if self.advance().unwrap().kind == TokenKind::LBracket {
-                return Ok(self.parse_index(Expr::List(expressions))?);
-            }
-
-            self.position -= 1;
-        }
-
-        Ok(Expr::List(expressions))
+        Ok(ExprKind::List(expressions))
     }
 
-    fn parse_index(&mut self, expr: Expr) -> Result<Expr> {
+    fn parse_index(&mut self, expr: Expr) -> Result<ExprKind> {
         let index = self.parse_expr()?;
 
         self.expect(TokenKind::RBracket)?;
 
-        Ok(Expr::Index(Box::new(expr), Box::new(index)))
+        Ok(ExprKind::Index(Box::new(expr), Box::new(index)))
     }
 
-    fn parse_dict(&mut self) -> Result<Expr> {
+    fn parse_dict(&mut self) -> Result<ExprKind> {
         let mut dict: HashMap<String, Box<Expr>> = HashMap::new();
 
-        while !self.is_at_end() && self.current().kind != TokenKind::RBrace {
+        while !self.is_at_end() && !self.check(&TokenKind::RBrace) {
             let key = match self.parse_expr()? {
-                Expr::String(key) => key,
+                Expr {
+                    kind: ExprKind::String(key),
+                    ..
+                } => key,
                 unexpected => {
-                    return Err(FlushError(
-                        self.file_path.clone(),
-                        self.previous().line,
-                        format!("Expected String, found {:?}", unexpected),
+                    return Err(FlushError::new(
+                        unexpected.span,
+                        format!("Expected String, found {:?}", unexpected.kind),
                     ))
                 }
             };
@@ -474,73 +519,83 @@ impl<'a> Parser<'a> {
 
             dict.insert(key, Box::from(self.parse_expr()?));
 
-            if self.current().kind == TokenKind::RBrace {
+            if self.check(&TokenKind::RBrace) {
                 break;
             }
 
             self.expect(TokenKind::Comma)?;
         }
 
-        self.advance();
+        self.expect(TokenKind::RBrace)?;
 
-        if !self.is_at_end() {
-            if self.advance().unwrap().kind == TokenKind::LBracket {
-                return Ok(self.parse_index(Expr::Dictionnary(dict))?);
-            }
+        Ok(ExprKind::Dictionnary(dict))
+    }
 
-            self.position -= 1;
-        }
+    fn parse_bin_op(&mut self, min_precedence: u8) -> Result<Expr> {
+        let mut left = self.parse_primary()?;
 
-        Ok(Expr::Dictionnary(dict))
-    }
+        while !self.is_at_end() {
+            let op = match self.current().kind {
+                TokenKind::Op(op) => BinOp::from(&op),
+                _ => break,
+            };
 
-    fn parse_number(&mut self) -> Result<Expr> {
-        Ok(match self.parse_expr()? {
-            Expr::Int(int) => Expr::Int(int),
-            Expr::Float(float) => Expr::Float(float),
-            unexpected => {
-                return Err(FlushError(
-                    self.file_path.clone(),
-                    self.previous().line,
-                    format!("Expected Number, found {:?}", unexpected),
-                ))
+            let (precedence, right_associative) = op.precedence();
+
+            if precedence < min_precedence {
+                break;
             }
-        })
-    }
 
-    fn parse_bin_op(&mut self, expr: Expr, bin_op: Op) -> Result<Expr> {
-        let boxed_expr = Box::new(expr);
-        let parsed_expr = Box::new(self.parse_expr()?);
-
-        Ok(Expr::BinOp(
-            match bin_op {
-                Op::Add => BinOp::Add,
-                Op::Sub => BinOp::Sub,
-                Op::Mul => BinOp::Mul,
-                Op::Div => BinOp::Div,
-                Op::FloorDiv => BinOp::FloorDiv,
-                Op::Mod => BinOp::Mod,
-                Op::Pow => BinOp::Pow,
-                Op::Lt => BinOp::Lt,
-                Op::Gt => BinOp::Gt,
-                Op::Eq => BinOp::Eq,
-                Op::Ne => BinOp::Ne,
-                Op::Le => BinOp::Le,
-                Op::Ge => BinOp::Ge,
-                _ => unreachable!(),
-            },
-            boxed_expr,
-            parsed_expr,
-        ))
+            self.position += 1;
+
+            let right = self.parse_bin_op(if right_associative {
+                precedence
+            } else {
+                precedence + 1
+            })?;
+
+            let span = left.span.to(&right.span);
+
+            left = Expr {
+                kind: ExprKind::BinOp(op, Box::new(left), Box::new(right)),
+                span,
+            };
+        }
+
+        Ok(left)
     }
 
-    fn parse_func_call(&mut self, id: String) -> Result<Expr> {
+    fn parse_func_call(&mut self, function: Expr) -> Result<ExprKind> {
         let mut args: Vec<Box<Expr>> = vec![];
+        let mut named_args: Vec<(String, Box<Expr>)> = vec![];
+
+        while !self.is_at_end() && !self.check(&TokenKind::RParen) {
+            let is_named = matches!(
+                (
+                    self.tokens.get(self.position).map(|token| &token.kind),
+                    self.tokens.get(self.position + 1).map(|token| &token.kind),
+                ),
+                (Some(TokenKind::Ident(_)), Some(TokenKind::Assign))
+            );
+
+            if is_named {
+                let id = match self.advance().unwrap().kind {
+                    TokenKind::Ident(id) => id,
+                    _ => unreachable!(),
+                };
+
+                self.position += 1;
+                named_args.push((id, Box::new(self.parse_expr()?)));
+            } else if !named_args.is_empty() {
+                return Err(FlushError::new(
+                    self.current().span,
+                    "Positional arguments cannot follow named arguments".to_string(),
+                ));
+            } else {
+                args.push(Box::new(self.parse_expr()?));
+            }
 
-        while !self.is_at_end() && self.current().kind != TokenKind::RParen {
-            args.push(Box::new(self.parse_expr()?));
-
-            if self.current().kind == TokenKind::RParen {
+            if self.check(&TokenKind::RParen) {
                 break;
             }
 
@@ -549,35 +604,27 @@ impl<'a> Parser<'a> {
 
         self.expect(TokenKind::RParen)?;
 
-        if !self.is_at_end() {
-            if self.advance().unwrap().kind == TokenKind::LBracket {
-                return Ok(self.parse_index(Expr::Call(id, args))?);
-            }
-
-            self.position -= 1;
-        }
-
-        Ok(Expr::Call(id, args))
+        Ok(ExprKind::Call(Box::new(function), args, named_args))
     }
 
-    fn parse_var_set(&mut self, var: String) -> Result<Statement> {
+    fn parse_var_set(&mut self, var: String) -> Result<StatementKind> {
         let value = self.parse_expr()?;
 
-        Ok(Statement::VarSet(var, value))
+        Ok(StatementKind::VarSet(var, value, Resolution::default()))
     }
 
-    fn parse_index_set(&mut self, expr: Expr, index: Expr) -> Result<Statement> {
+    fn parse_index_set(&mut self, expr: Expr, index: Expr) -> Result<StatementKind> {
         let value = self.parse_expr()?;
 
-        Ok(Statement::IndexSet(expr, index, value))
+        Ok(StatementKind::IndexSet(expr, index, value))
     }
 
-    pub fn parse(&mut self) -> Result<&Vec<Statement>> {
+    pub fn parse(mut self) -> Result<Vec<Statement>> {
         while !self.is_at_end() {
             let statement = self.parse_statement()?;
-            self.statements.push(statement.clone());
+            self.statements.push(statement);
         }
 
-        Ok(&self.statements)
+        Ok(self.statements)
     }
 }
@@ -0,0 +1,97 @@
+use crate::lexing::lexer::Lexer;
+use crate::parsing::parser::Parser;
+use crate::parsing::typing::*;
+use std::path::PathBuf;
+
+// Renders the shape of an expression as an s-expression, e.g. `(+ 1 (* 2 3))`
+fn tree(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Int(int) => int.to_string(),
+        ExprKind::Var(id, _) => id.clone(),
+        ExprKind::BinOp(op, left, right) => {
+            let op = match op {
+                BinOp::Add => "+",
+                BinOp::Sub => "-",
+                BinOp::Mul => "*",
+                BinOp::Div => "/",
+                BinOp::FloorDiv => "//",
+                BinOp::Mod => "%",
+                BinOp::Pow => "^",
+                BinOp::Lt => "<",
+                BinOp::Gt => ">",
+                BinOp::Eq => "==",
+                BinOp::Ne => "/=",
+                BinOp::Le => "<=",
+                BinOp::Ge => ">=",
+                BinOp::And => "and",
+                BinOp::Or => "or",
+            };
+
+            format!("({} {} {})", op, tree(left), tree(right))
+        }
+        ExprKind::Unary(UnaryOp::Neg, expr) => format!("(neg {})", tree(expr)),
+        ExprKind::Unary(UnaryOp::Not, expr) => format!("(not {})", tree(expr)),
+        ExprKind::Call(function, args, _) => format!(
+            "(call {}{})",
+            tree(function),
+            args.iter()
+                .map(|arg| format!(" {}", tree(arg)))
+                .collect::<String>()
+        ),
+        ExprKind::Index(list, index) => format!("(index {} {})", tree(list), tree(index)),
+        unexpected => panic!("Unexpected expression {:?}", unexpected),
+    }
+}
+
+fn parse_expr(source: &str) -> String {
+    let mut lexer = Lexer::new(source, PathBuf::from("<test>"));
+    let tokens = lexer.tokenize().unwrap();
+    let parser = Parser::new(tokens);
+
+    match &parser.parse().unwrap()[..] {
+        [Statement {
+            kind: StatementKind::Expr(expr),
+            ..
+        }] => tree(expr),
+        unexpected => panic!("Expected one expression, found {:?}", unexpected),
+    }
+}
+
+#[test]
+fn precedence() {
+    assert_eq!(parse_expr("1 + 2 * 3"), "(+ 1 (* 2 3))");
+    assert_eq!(parse_expr("1 * 2 + 3"), "(+ (* 1 2) 3)");
+    assert_eq!(parse_expr("a + b % c"), "(+ a (% b c))");
+    assert_eq!(parse_expr("a * b ^ c"), "(* a (^ b c))");
+    assert_eq!(parse_expr("a < b + 1"), "(< a (+ b 1))");
+    assert_eq!(parse_expr("a == b * 2"), "(== a (* b 2))");
+    assert_eq!(parse_expr("a == b and c < d"), "(and (== a b) (< c d))");
+    assert_eq!(parse_expr("a or b and c"), "(or a (and b c))");
+}
+
+#[test]
+fn associativity() {
+    assert_eq!(parse_expr("a - b - c"), "(- (- a b) c)");
+    assert_eq!(parse_expr("a / b / c"), "(/ (/ a b) c)");
+    assert_eq!(parse_expr("a - b + c"), "(+ (- a b) c)");
+    assert_eq!(parse_expr("a ^ b ^ c"), "(^ a (^ b c))");
+    assert_eq!(parse_expr("a or b or c"), "(or (or a b) c)");
+}
+
+#[test]
+fn grouping() {
+    assert_eq!(parse_expr("(1 + 2) * 3"), "(* (+ 1 2) 3)");
+    assert_eq!(parse_expr("a - (b - c)"), "(- a (- b c))");
+    assert_eq!(parse_expr("((a))"), "a");
+    assert_eq!(parse_expr("(a ^ b) ^ c"), "(^ (^ a b) c)");
+}
+
+#[test]
+fn operands() {
+    assert_eq!(parse_expr("-a ^ 2"), "(neg (^ a 2))");
+    assert_eq!(parse_expr("not a and b"), "(and (not a) b)");
+    assert_eq!(
+        parse_expr("f(1 + 2) * xs[0]"),
+        "(* (call f (+ 1 2)) (index xs 0))"
+    );
+}
@@ -1,35 +1,86 @@
+use crate::error::Span;
+use crate::lexing::typing::Op;
+use num_bigint::BigInt;
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 
 #[derive(Clone, Debug, PartialEq)]
-pub enum Statement {
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatementKind {
     If(Expr, Vec<Box<Statement>>, Vec<Box<Statement>>), // if (...) { ... }
-    VarDef(String, Expr),                               // def user_id = ...
-    FuncDef(String, Vec<String>, Vec<Statement>),       // def factorial(...) { ... }
-    VarSet(String, Expr),                               // user_id = ...
+    VarDef(String, Expr, Resolution),                   // def user_id = ...
+    FuncDef(String, Vec<Param>, Rc<[Statement]>, Resolution), // def factorial(...) { ... }
+    VarSet(String, Expr, Resolution),                   // user_id = ...
     IndexSet(Expr, Expr, Expr),                         // {}["name"] = marcello
     Return(Expr),                                       // return ...
     While(Expr, Vec<Box<Statement>>),                   // while (true) { ... }
-    For(String, Expr, Vec<Box<Statement>>),             // for (i in ...) { ... }
+    For(String, Span, Expr, Vec<Box<Statement>>, Resolution), // for (i in ...) { ... }
     Break,                                              // break
+    Continue,                                           // continue
     Load(String),                                       // load "..."
     Expr(Expr),
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Resolution {
+    #[default]
+    Global,
+    Local(usize, usize), // (depth, slot)
+}
+
 #[derive(Clone, Debug, PartialEq)]
-pub enum Expr {
-    String(String),                          // "Hello, flush!"
-    Int(i32),                                // -32
-    Float(f64),                              // 18.25
-    Boolean(bool),                           // false
-    Var(String),                             // userId
-    Call(String, Vec<Box<Expr>>),            // printLn(...)
-    List(Vec<Box<Expr>>),                    // [1, 2, 3]
-    Dictionnary(HashMap<String, Box<Expr>>), // { "key": "value" }
-    Index(Box<Expr>, Box<Expr>),             // [1, 2, 3, 4][0] or {"name": "flush"}["name"]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExprKind {
+    String(String),                                            // "Hello, flush!"
+    Int(i64),                                                  // -32
+    BigInt(BigInt),                                            // 36893488147419103232
+    Float(f64),                                                // 18.25
+    Boolean(bool),                                             // false
+    Var(String, Resolution),                                   // userId
+    Call(Box<Expr>, Vec<Box<Expr>>, Vec<(String, Box<Expr>)>), // greet("flush", greeting = "hi")
+    List(Vec<Box<Expr>>),                                      // [1, 2, 3]
+    Dictionnary(HashMap<String, Box<Expr>>),                   // { "key": "value" }
+    Index(Box<Expr>, Box<Expr>), // [1, 2, 3, 4][0] or {"name": "flush"}["name"]
+    Lambda(Vec<Param>, Rc<[Statement]>), // def (x) { return x * 2 }
     BinOp(BinOp, Box<Expr>, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param {
+    pub kind: ParamKind,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug, PartialEq)]
+pub enum ParamKind {
+    Required(String),       // name
+    Optional(String, Expr), // greeting = "hi"
+    Rest(String),           // ...names
+}
+
+impl Param {
+    pub fn name(&self) -> &str {
+        match &self.kind {
+            ParamKind::Required(name) | ParamKind::Optional(name, _) | ParamKind::Rest(name) => {
+                name
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum BinOp {
     Add,      // +
     Sub,      // -
@@ -44,4 +95,71 @@ pub enum BinOp {
     Ne,       // /=
     Le,       // <=
     Ge,       // >=
+    And,      // and
+    Or,       // or
+}
+
+impl BinOp {
+    pub fn precedence(&self) -> (u8, bool) {
+        match self {
+            BinOp::Or => (1, false),
+            BinOp::And => (2, false),
+            BinOp::Lt | BinOp::Gt | BinOp::Eq | BinOp::Ne | BinOp::Le | BinOp::Ge => (3, false),
+            BinOp::Add | BinOp::Sub => (4, false),
+            BinOp::Mul | BinOp::Div | BinOp::FloorDiv | BinOp::Mod => (5, false),
+            BinOp::Pow => (6, true),
+        }
+    }
+}
+
+impl From<&Op> for BinOp {
+    fn from(op: &Op) -> Self {
+        match op {
+            Op::Add => BinOp::Add,
+            Op::Sub => BinOp::Sub,
+            Op::Mul => BinOp::Mul,
+            Op::Div => BinOp::Div,
+            Op::FloorDiv => BinOp::FloorDiv,
+            Op::Mod => BinOp::Mod,
+            Op::Pow => BinOp::Pow,
+            Op::Lt => BinOp::Lt,
+            Op::Gt => BinOp::Gt,
+            Op::Eq => BinOp::Eq,
+            Op::Ne => BinOp::Ne,
+            Op::Le => BinOp::Le,
+            Op::Ge => BinOp::Ge,
+            Op::And => BinOp::And,
+            Op::Or => BinOp::Or,
+        }
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::FloorDiv => "//",
+            BinOp::Mod => "%",
+            BinOp::Pow => "^",
+            BinOp::Lt => "<",
+            BinOp::Gt => ">",
+            BinOp::Eq => "==",
+            BinOp::Ne => "/=",
+            BinOp::Le => "<=",
+            BinOp::Ge => ">=",
+            BinOp::And => "and",
+            BinOp::Or => "or",
+        };
+
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnaryOp {
+    Neg, // -
+    Not, // not
 }
@@ -0,0 +1,178 @@
+use crate::error::{style, Result};
+use crate::interpreting::interpreter::Interpreter;
+use crate::interpreting::modules::ModuleRegistry;
+use crate::interpreting::typing::Literal;
+use crate::lexing::lexer::Lexer;
+use crate::lexing::typing::TokenKind;
+use crate::parsing::parser::Parser;
+use ansi_term::Colour::{Blue, Red};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::env;
+use std::path::PathBuf;
+
+const PROMPT: &str = ">>> ";
+const CONTINUATION_PROMPT: &str = "... ";
+const HISTORY_FILE: &str = ".flush_history";
+
+enum Command {
+    Quit(i32),
+    Reset,
+}
+
+pub fn start() -> i32 {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!(
+                "{}: Could not start the REPL: {}",
+                style(Red.bold()).paint("error"),
+                e
+            );
+            return 1;
+        }
+    };
+    let history = env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+
+    if let Some(history) = &history {
+        let _ = editor.load_history(history);
+    }
+
+    println!(
+        "Flush {} REPL, type `:help` for help",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let code = loop {
+        let mut interpreter = Interpreter::repl(PathBuf::from("<repl>"), ModuleRegistry::shared());
+
+        match session(&mut editor, &mut interpreter) {
+            Command::Quit(code) => break code,
+            Command::Reset => println!("The interpreter has been reset"),
+        }
+    };
+
+    if let Some(history) = &history {
+        let _ = editor.save_history(history);
+    }
+
+    code
+}
+
+fn session(editor: &mut DefaultEditor, interpreter: &mut Interpreter) -> Command {
+    loop {
+        let input = match read_input(editor) {
+            Some(input) => input,
+            None => return Command::Quit(0),
+        };
+
+        let trimmed = input.trim();
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let _ = editor.add_history_entry(trimmed);
+
+        if let Some(command) = trimmed.strip_prefix(':') {
+            match command.split_once(' ').unwrap_or((command, "")) {
+                ("quit", _) | ("q", _) => return Command::Quit(0),
+                ("reset", _) => return Command::Reset,
+                ("vars", _) => print_vars(interpreter),
+                ("load", path) if !path.trim().is_empty() => {
+                    if let Err(e) = interpreter.load(path.trim()) {
+                        eprintln!("{}", e);
+                    }
+                }
+                ("help", _) => print_help(),
+                _ => eprintln!(
+                    "{}: Unknown command `:{}`, type `:help` for help",
+                    style(Red.bold()).paint("error"),
+                    command
+                ),
+            }
+
+            continue;
+        }
+
+        match eval(interpreter, &input) {
+            Ok(Some(Literal::None)) | Ok(None) => {}
+            Ok(Some(literal)) => println!("{}", literal),
+            Err(e) => match e.exit_code {
+                Some(code) => return Command::Quit(code),
+                None => eprintln!("{}", e),
+            },
+        }
+    }
+}
+
+fn read_input(editor: &mut DefaultEditor) -> Option<String> {
+    let mut input = String::new();
+
+    loop {
+        let prompt = if input.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+
+        match editor.readline(prompt) {
+            Ok(line) => {
+                input.push_str(&line);
+                input.push('\n');
+
+                if depth(&input) <= 0 {
+                    return Some(input);
+                }
+            }
+            Err(ReadlineError::Interrupted) => input.clear(),
+            Err(ReadlineError::Eof) if !input.is_empty() => return Some(input),
+            Err(_) => return None,
+        }
+    }
+}
+
+fn depth(input: &str) -> i32 {
+    let mut lexer = Lexer::new(input, PathBuf::from("<repl>"));
+
+    match lexer.tokenize() {
+        Ok(tokens) => tokens
+            .iter()
+            .map(|token| match token.kind {
+                TokenKind::LParen | TokenKind::LBrace | TokenKind::LBracket => 1,
+                TokenKind::RParen | TokenKind::RBrace | TokenKind::RBracket => -1,
+                _ => 0,
+            })
+            .sum(),
+        Err(_) => 0,
+    }
+}
+
+fn eval(interpreter: &mut Interpreter, input: &str) -> Result<Option<Literal>> {
+    let mut lexer = Lexer::new(input, PathBuf::from("<repl>"));
+    let tokens = lexer.tokenize()?;
+
+    let statements = Parser::new(tokens).parse()?;
+
+    interpreter.eval_input(statements)
+}
+
+fn print_vars(interpreter: &Interpreter) {
+    let globals = interpreter.get_stack();
+    let mut names: Vec<&String> = globals.keys().collect();
+
+    names.sort();
+
+    println!("{}", style(Blue.bold()).paint("globals"));
+
+    for name in names {
+        println!("  {} = {}", name, globals[name].literal);
+    }
+}
+
+fn print_help() {
+    println!(":load <file>  load the definitions of a file");
+    println!(":vars         show the global variables");
+    println!(":reset        start again with a fresh interpreter");
+    println!(":quit         leave the REPL");
+}
@@ -0,0 +1,140 @@
+use crate::error::{style, Result};
+use crate::flush::{self, Backend};
+use crate::interpreting::interpreter::Interpreter;
+use crate::interpreting::modules::ModuleRegistry;
+use crate::interpreting::typing::{Literal, Variable};
+use ansi_term::Colour::{Green, Red};
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let entries = match read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => return Err(format!("Could not read `{}`: {}", path.display(), e).into()),
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir()
+            || path
+                .extension()
+                .is_some_and(|extension| extension == "flush")
+        {
+            collect_files(&path, files)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn test_functions(globals: Vec<(String, Variable)>) -> Vec<(String, Variable)> {
+    let mut tests: Vec<(String, Variable)> = globals
+        .into_iter()
+        .filter(|(id, variable)| {
+            id.starts_with("test") && matches!(variable.literal, Literal::Function(..))
+        })
+        .collect();
+
+    tests.sort_by_key(|(_, variable)| variable.span.line);
+
+    tests
+}
+
+fn run_file(file_path: &Path, passed: &mut usize, failed: &mut usize) -> Result<()> {
+    let (file_path, content) = flush::read_file(&file_path.to_string_lossy())?;
+    let modules = ModuleRegistry::shared();
+    let globals = flush::run_source(
+        &content,
+        file_path.clone(),
+        &modules,
+        false,
+        vec![],
+        Backend::Interpreter,
+    )?;
+    let mut interpreter = Interpreter::new(vec![], file_path.clone(), modules, false);
+
+    for (id, variable) in test_functions(globals.into_iter().collect()) {
+        let result = interpreter.call_function(variable.literal, vec![], vec![], variable.span);
+
+        match result {
+            Ok(_) => {
+                *passed += 1;
+                println!(
+                    "test {}::{} ... {}",
+                    file_path.display(),
+                    id,
+                    style(Green.normal()).paint("ok")
+                );
+            }
+            Err(e) => {
+                *failed += 1;
+                println!(
+                    "test {}::{} ... {}",
+                    file_path.display(),
+                    id,
+                    style(Red.normal()).paint("FAILED")
+                );
+                eprintln!("{}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn run(paths: &[String]) -> i32 {
+    let paths = if paths.is_empty() {
+        vec![".".to_string()]
+    } else {
+        paths.to_vec()
+    };
+    let mut files = vec![];
+
+    for path in paths {
+        if let Err(e) = collect_files(Path::new(&path), &mut files) {
+            eprintln!("{}", e);
+            return 1;
+        }
+    }
+
+    let (mut passed, mut failed) = (0, 0);
+
+    for file in files {
+        if let Err(e) = run_file(&file, &mut passed, &mut failed) {
+            failed += 1;
+            println!(
+                "test {} ... {}",
+                file.display(),
+                style(Red.normal()).paint("FAILED")
+            );
+            eprintln!("{}", e);
+        }
+    }
+
+    let status = if failed == 0 {
+        style(Green.normal()).paint("ok")
+    } else {
+        style(Red.normal()).paint("FAILED")
+    };
+
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        status, passed, failed
+    );
+
+    if failed == 0 {
+        0
+    } else {
+        1
+    }
+}
@@ -0,0 +1,307 @@
+use crate::error::FlushError;
+use crate::interpreting::typing::Literal;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::rc::Rc;
+
+// A flush value as seen from Rust. It wraps the interpreter's `Literal` so that
+// lists, dictionaries and functions stay opaque and their representation can change
+#[derive(Clone, Debug)]
+pub struct Value(pub(crate) Rc<Literal>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Kind {
+    String,
+    Int,
+    Float,
+    Boolean,
+    List,
+    Dictionnary,
+    Function,
+    None,
+}
+
+impl Value {
+    pub(crate) fn new(literal: Literal) -> Self {
+        Value(Rc::new(literal))
+    }
+
+    pub(crate) fn literal(&self) -> Literal {
+        (*self.0).clone()
+    }
+
+    pub fn none() -> Self {
+        Value::new(Literal::None)
+    }
+
+    pub fn kind(&self) -> Kind {
+        match &*self.0 {
+            Literal::String(_) => Kind::String,
+            Literal::Int(_) | Literal::BigInt(_) => Kind::Int,
+            Literal::Float(_) => Kind::Float,
+            Literal::Boolean(_) => Kind::Boolean,
+            Literal::List(_) => Kind::List,
+            Literal::Dictionnary(_) => Kind::Dictionnary,
+            Literal::Function(_) | Literal::Closure(_) | Literal::Host(_) => Kind::Function,
+            Literal::None => Kind::None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        self.0.type_name()
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(*self.0, Literal::None)
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match *self.0 {
+            Literal::Int(int) => Some(int),
+            _ => None,
+        }
+    }
+
+    pub fn as_bigint(&self) -> Option<BigInt> {
+        self.0.to_bigint()
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match *self.0 {
+            Literal::Float(float) => Some(float),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self.0 {
+            Literal::Boolean(boolean) => Some(boolean),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match &*self.0 {
+            Literal::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    // Lists and dictionaries are shared, so these return a snapshot of their elements
+    pub fn to_list(&self) -> Option<Vec<Value>> {
+        match &*self.0 {
+            Literal::List(list) => Some(
+                list.borrow()
+                    .iter()
+                    .map(|literal| Value::new(*literal.clone()))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    pub fn to_dictionnary(&self) -> Option<HashMap<String, Value>> {
+        match &*self.0 {
+            Literal::Dictionnary(dict) => Some(
+                dict.borrow()
+                    .iter()
+                    .map(|(key, literal)| (key.clone(), Value::new(*literal.clone())))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::none()
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+fn unexpected(expected: &str, found: &Value) -> FlushError {
+    format!("Expected {}, found {}", expected, found).into()
+}
+
+impl From<i32> for Value {
+    fn from(int: i32) -> Self {
+        Value::new(Literal::Int(i64::from(int)))
+    }
+}
+
+impl From<i64> for Value {
+    fn from(int: i64) -> Self {
+        Value::new(Literal::Int(int))
+    }
+}
+
+impl From<BigInt> for Value {
+    fn from(int: BigInt) -> Self {
+        Value::new(Literal::from(int))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(float: f64) -> Self {
+        Value::new(Literal::Float(float))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(boolean: bool) -> Self {
+        Value::new(Literal::Boolean(boolean))
+    }
+}
+
+impl From<String> for Value {
+    fn from(string: String) -> Self {
+        Value::new(Literal::String(string.into()))
+    }
+}
+
+impl From<&str> for Value {
+    fn from(string: &str) -> Self {
+        Value::new(Literal::String(string.into()))
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(list: Vec<T>) -> Self {
+        Value::new(Literal::list(
+            list.into_iter()
+                .map(|value| Box::new(value.into().literal()))
+                .collect(),
+        ))
+    }
+}
+
+impl<T: Into<Value>> From<HashMap<String, T>> for Value {
+    fn from(dict: HashMap<String, T>) -> Self {
+        Value::new(Literal::dictionnary(
+            dict.into_iter()
+                .map(|(key, value)| (key, Box::new(value.into().literal())))
+                .collect(),
+        ))
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(option: Option<T>) -> Self {
+        match option {
+            Some(value) => value.into(),
+            None => Value::none(),
+        }
+    }
+}
+
+impl TryFrom<Value> for i32 {
+    type Error = FlushError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let int = i64::try_from(value)?;
+
+        i32::try_from(int).map_err(|_| format!("The Int {} does not fit in 32 bits", int).into())
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = FlushError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match &*value.0 {
+            Literal::Int(int) => Ok(*int),
+            Literal::BigInt(int) => Err(format!("The Int {} does not fit in 64 bits", int).into()),
+            _ => Err(unexpected("Int", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for BigInt {
+    type Error = FlushError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match &*value.0 {
+            Literal::Int(int) => Ok(BigInt::from(*int)),
+            Literal::BigInt(int) => Ok(int.clone()),
+            _ => Err(unexpected("Int", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = FlushError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match &*value.0 {
+            Literal::Float(float) => Ok(*float),
+            Literal::Int(int) => Ok(*int as f64),
+            Literal::BigInt(int) => Ok(int.to_f64().unwrap()),
+            _ => Err(unexpected("Float", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = FlushError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value.as_bool().ok_or_else(|| unexpected("Boolean", &value))
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = FlushError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            Some(string) => Ok(string.to_string()),
+            None => Err(unexpected("String", &value)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = FlushError>> TryFrom<Value> for Vec<T> {
+    type Error = FlushError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value.to_list() {
+            Some(list) => list.into_iter().map(T::try_from).collect(),
+            None => Err(unexpected("List", &value)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = FlushError>> TryFrom<Value> for HashMap<String, T> {
+    type Error = FlushError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value.to_dictionnary() {
+            Some(dict) => dict
+                .into_iter()
+                .map(|(key, value)| Ok((key, T::try_from(value)?)))
+                .collect(),
+            None => Err(unexpected("Dictionnary", &value)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = FlushError>> TryFrom<Value> for Option<T> {
+    type Error = FlushError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match &*value.0 {
+            Literal::None => Ok(None),
+            _ => T::try_from(value).map(Some),
+        }
+    }
+}
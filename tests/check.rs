@@ -0,0 +1,138 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn check(input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_flush"))
+        .args(["check", "-"])
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn diagnostics(input: &str) -> (bool, Vec<String>) {
+    let output = check(input);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let headers = stderr
+        .lines()
+        .filter(|line| line.starts_with("error") || line.starts_with("warning"))
+        .map(|line| line.to_string())
+        .collect();
+
+    (output.status.success(), headers)
+}
+
+#[test]
+fn undefined_names() {
+    let (success, headers) = diagnostics(
+        "def main() {\n    def count = 1\n    printLn(cout)\n    prnt(count)\n    printLn(later())\n}\ndef later() { return 1 }\n",
+    );
+
+    assert!(!success);
+    assert_eq!(
+        headers,
+        vec![
+            "error[undefined]: The variable `cout` cannot be found!",
+            "error[undefined]: Undefined function `prnt`!",
+        ]
+    );
+}
+
+#[test]
+fn duplicates_and_loops() {
+    let (success, headers) =
+        diagnostics("def main() {\n    def a = 1\n    def a = 2\n    printLn(a)\n    break\n}\n");
+
+    assert!(!success);
+    assert_eq!(
+        headers,
+        vec![
+            "error[duplicate]: The `a` variable already exists!",
+            "error[outside_loop]: The break keyword cannot be used outside a loop!",
+        ]
+    );
+}
+
+#[test]
+fn unused_and_unreachable() {
+    let (success, headers) = diagnostics(
+        "def f(a, _b, c) {\n    def d = 1\n    return a\n    printLn(c)\n}\ndef main() {\n    if (true) { def e = 1 } else { def e = 2 }\n    printLn(e)\n    f(1, 2, 3)\n}\n",
+    );
+
+    assert!(success);
+    assert_eq!(
+        headers,
+        vec![
+            "warning[unused]: The variable `d` is never used",
+            "warning[unreachable]: Unreachable code",
+        ]
+    );
+}
+
+#[test]
+fn loop_variable_spans() {
+    let output = check(
+        "def main() {\n    def n = 1\n    for (  i in [1]) {}\n    for (n in [2]) {\n        printLn(n)\n    }\n}\n",
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stderr.contains(
+        " --> <stdin>:3:12
+  |
+3 |     for (  i in [1]) {}
+  |            ^
+"
+    ));
+    assert!(stderr.contains(
+        " --> <stdin>:4:10
+  |
+2 |     def n = 1
+  |     --------- `n` is first defined here
+...
+4 |     for (n in [2]) {
+  |          ^
+"
+    ));
+}
+
+#[test]
+fn pragmas() {
+    let (success, headers) = diagnostics(
+        "# flush: allow(unused) warn(undefined)\n# flush: deny(unreachable, unknown)\ndef main() {\n    def a = 1\n    return b\n    printLn(a)\n}\n",
+    );
+
+    assert!(!success);
+    assert_eq!(
+        headers,
+        vec![
+            "warning: Unknown lint `unknown`",
+            "warning[undefined]: The variable `b` cannot be found!",
+            "error[unreachable]: Unreachable code",
+        ]
+    );
+}
+
+#[test]
+fn examples() {
+    for example in ["factorial", "fizz_buzz", "hello_world", "higher_order"] {
+        let path = format!("{}/examples/{}.flush", env!("CARGO_MANIFEST_DIR"), example);
+        let output = Command::new(env!("CARGO_BIN_EXE_flush"))
+            .args(["check", &path])
+            .output()
+            .unwrap();
+
+        assert!(output.status.success(), "{}", path);
+        assert!(output.stderr.is_empty(), "{}", path);
+    }
+}
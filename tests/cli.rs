@@ -0,0 +1,266 @@
+use std::fs::{create_dir_all, remove_dir_all, write};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+// A scratch directory for the scripts of one test
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("flush-{}-{}", name, std::process::id()));
+    create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn flush(args: &[&str], input: &str) -> Output {
+    run(Command::new(env!("CARGO_BIN_EXE_flush")).args(args), input)
+}
+
+fn run(command: &mut Command, input: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[test]
+fn diagnostics() {
+    let dir = scratch("diagnostics");
+    let script = dir.join("main.flush");
+    let run = |source: &str| {
+        write(&script, source).unwrap();
+        stderr(&flush(&[script.to_str().unwrap()], ""))
+    };
+
+    let redefined = run("def main() {\n    def count = 1\n    def count = 2\n}\n");
+    let unclosed = run("def main() {\n    printLn(1 +\n}\n");
+    let misspelled = run("def main() {\n    def count = 1\n    printLn(cout, count)\n}\n");
+    remove_dir_all(&dir).unwrap();
+
+    assert!(!redefined.contains('\u{1b}'), "{}", redefined);
+    assert!(redefined.ends_with(&format!(
+        "error: The `count` variable already exists!
+ --> {}:3:5
+  |
+2 |     def count = 1
+  |     ------------- `count` is first defined here
+3 |     def count = 2
+  |     ^^^^^^^^^^^^^
+",
+        script.display()
+    )));
+    assert_eq!(
+        unclosed,
+        format!(
+            "error: Expected Expression, found RBrace
+ --> {}:3:1
+  |
+3 | }}
+  | ^
+",
+            script.display()
+        )
+    );
+    assert!(misspelled.contains(
+        "3 |     printLn(cout, count)
+  |             ^^^^
+  = help: a similar name exists: `count`
+"
+    ));
+}
+
+#[test]
+fn tracebacks() {
+    let dir = scratch("tracebacks");
+
+    let library = dir.join("library.flush");
+    write(
+        &library,
+        "def check(value) {\n    return value + \"s\"\n}\n",
+    )
+    .unwrap();
+
+    let script = dir.join("main.flush");
+    write(
+        &script,
+        format!(
+            "load \"{}\"\n\ndef outer() {{\n    return check(1)\n}}\n\ndef main() {{\n    outer()\n}}\n",
+            library.display()
+        ),
+    )
+    .unwrap();
+
+    let loaded = stderr(&flush(&[script.to_str().unwrap()], ""));
+
+    let recursive = dir.join("recursive.flush");
+    write(
+        &recursive,
+        "def f(n) {\n    if (n == 0) {\n        return 1 + \"s\"\n    }\n\n    return f(n - 1)\n}\n\ndef main() {\n    f(50)\n}\n",
+    )
+    .unwrap();
+
+    let elided = stderr(&flush(&[recursive.to_str().unwrap()], ""));
+    remove_dir_all(&dir).unwrap();
+
+    assert!(loaded.starts_with(&format!(
+        "Traceback (most recent call last):
+  File \"{main}\", line 7, in <module>
+    def main() {{
+  File \"{main}\", line 8, in main
+    outer()
+  File \"{main}\", line 4, in outer
+    return check(1)
+  File \"{library}\", line 2, in check
+    return value + \"s\"
+error: Cannot add 1 and \"s\"
+",
+        main = script.display(),
+        library = library.display()
+    )));
+
+    let frames = elided
+        .lines()
+        .filter(|line| line.starts_with("  File "))
+        .count();
+
+    assert_eq!(frames, 20);
+    assert!(elided.contains("  ... 33 frames elided ...\n"));
+    assert!(elided.contains(&format!(
+        "  File \"{}\", line 3, in f\n    return 1 + \"s\"\nerror:",
+        recursive.display()
+    )));
+}
+
+#[test]
+fn repl() {
+    let home = scratch("repl");
+
+    let output = run(
+        Command::new(env!("CARGO_BIN_EXE_flush")).env("HOME", &home),
+        "def x = 2\nx * 21\ndef f(n) {\n    return n + 1\n}\nf(x)\n:vars\nprintLn(1 +)\n:reset\nx\n\"still\"\n:quit\nprintLn(\"unreachable\")\n",
+    );
+    let history = std::fs::read_to_string(home.join(".flush_history")).unwrap();
+    remove_dir_all(&home).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "Flush {} REPL, type `:help` for help
+42
+3
+globals
+  f = <function:f#n>
+  x = 2
+The interpreter has been reset
+\"still\"
+",
+            env!("CARGO_PKG_VERSION")
+        )
+    );
+
+    let stderr = stderr(&output);
+    assert!(stderr.contains("error: Expected Expression, found RParen"));
+    assert!(stderr.contains("error: The variable `x` cannot be found!"));
+    assert!(history.contains("x * 21"));
+}
+
+#[test]
+fn commands_and_exit_codes() {
+    let version = format!("flush {}\n", env!("CARGO_PKG_VERSION"));
+
+    for flag in ["--version", "-V"] {
+        let output = flush(&[flag], "");
+        assert!(output.status.success());
+        assert_eq!(stdout(&output), version);
+    }
+
+    let output = flush(&["--help"], "");
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("Usage: flush [options] [file] [args...]\n"));
+
+    let output = flush(&["--bogus"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("error: Unknown option `--bogus`\n"));
+
+    let output = flush(&["-e", "def main() { printLn(1 + 1) }"], "");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "2\n");
+
+    let output = flush(&["-"], "def main() { printLn(\"stdin\") }\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "\"stdin\"\n");
+
+    for (args, input) in [
+        (&["run", "-"][..], "def main() { \"\n"),
+        (&["run", "-"][..], "def main() { printLn(1 +) }\n"),
+        (&["run", "-"][..], "def main() { printLn(1 + \"a\") }\n"),
+        (&["check", "-"][..], "def main() { printLn(1 +) }\n"),
+        (&["run", "/nonexistent.flush"][..], ""),
+    ] {
+        let output = flush(args, input);
+        assert_eq!(output.status.code(), Some(1), "{:?} {}", args, input);
+        assert!(stderr(&output).contains("error"));
+    }
+
+    let output = flush(&["check", "-"], "def main() { printLn(1) }\n");
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn arguments_environment_and_exit() {
+    let script = "def stop() {
+    for (_i in [1, 2]) {
+        exit(7)
+    }
+}
+
+def main(args) {
+    printLn(args, args())
+    printLn(getEnv(\"FLUSH_TEST_VAR\"), getEnv(\"FLUSH_MISSING_VAR\"))
+    setEnv(\"FLUSH_TEST_VAR\", \"changed\")
+    printLn(getEnv(\"FLUSH_TEST_VAR\"))
+    stop()
+    printLn(\"unreachable\")
+}
+";
+    let output = run(
+        Command::new(env!("CARGO_BIN_EXE_flush"))
+            .args(["run", "-", "--", "one", "two words"])
+            .env("FLUSH_TEST_VAR", "hi")
+            .env_remove("FLUSH_MISSING_VAR"),
+        script,
+    );
+
+    assert_eq!(output.status.code(), Some(7));
+    assert_eq!(
+        stdout(&output),
+        "[\"one\", \"two words\"] [\"one\", \"two words\"]\n\"hi\" None\n\"changed\"\n"
+    );
+    assert_eq!(stderr(&output), "");
+
+    let output = flush(&["-", "x"], "def main() { printLn(args()) }\n");
+    assert_eq!(stdout(&output), "[\"x\"]\n");
+
+    let output = flush(&["run", "-"], "def main() { exit(1.5) }\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("error: Expected Integer, found 1.5\n"));
+}
@@ -0,0 +1,192 @@
+use flush::{Engine, Kind, Level};
+use std::fs::{create_dir_all, write};
+
+#[test]
+fn eval() {
+    let mut engine = Engine::new();
+
+    assert_eq!(engine.eval("1 + 2").unwrap().as_int(), Some(3));
+    assert!(engine.eval("def x = 4").unwrap().is_none());
+    assert_eq!(engine.eval("x * 2").unwrap().as_int(), Some(8));
+}
+
+#[test]
+fn call() {
+    let mut engine = Engine::new();
+
+    engine
+        .eval("def greet(name, greeting = \"Hello\") { return greeting + \", \" + name }")
+        .unwrap();
+
+    let greeting = engine.call("greet", vec!["flush".into()]).unwrap();
+    assert_eq!(greeting.as_str(), Some("Hello, flush"));
+
+    let error = engine.call("greet", vec![]).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Not enough arguments for `greet`, expected `1 to 2` given `0`: `name` is missing!"
+    );
+
+    let error = engine.call("missing", vec![]).unwrap_err();
+    assert_eq!(error.message, "The variable `missing` cannot be found!");
+}
+
+#[test]
+fn globals() {
+    let mut engine = Engine::new();
+
+    assert!(engine.get("limit").is_none());
+
+    engine.set("limit", 10.into());
+    engine.eval("def double() { return limit * 2 }").unwrap();
+    assert_eq!(engine.call("double", vec![]).unwrap().as_int(), Some(20));
+
+    engine.eval("limit = 21").unwrap();
+    assert_eq!(engine.get("limit").unwrap().as_int(), Some(21));
+    assert_eq!(engine.get("double").unwrap().kind(), Kind::Function);
+}
+
+#[test]
+fn errors() {
+    let mut engine = Engine::new();
+
+    let error = engine.eval("def f() {\n    return 1 +\n}").unwrap_err();
+    assert_eq!(error.level, Level::Error);
+    assert_eq!(error.span.line, 3);
+    assert_eq!(error.span.file.path.to_string_lossy(), "<eval>");
+
+    engine
+        .eval("def inner() { return [1][3] }\ndef outer() { return inner() }")
+        .unwrap();
+    let error = engine.call("outer", vec![]).unwrap_err();
+    let frames: Vec<&str> = error
+        .trace
+        .iter()
+        .map(|frame| frame.function.as_str())
+        .collect();
+    assert_eq!(frames, vec!["inner", "outer"]);
+    assert_eq!(error.span.line, 1);
+
+    let error = engine.eval("exit(3)").unwrap_err();
+    assert_eq!(error.exit_code, Some(3));
+}
+
+#[test]
+fn index_errors() {
+    let mut engine = Engine::new();
+
+    engine.eval("def s = \"héllo\"").unwrap();
+
+    for (source, message) in [
+        ("s[5]", "The list has a length of `5` but the index is `5`!"),
+        ("s[-1]", "The `-1` is not valid as an index!"),
+        (
+            "[1][3]",
+            "The list has a length of `1` but the index is `3`!",
+        ),
+        ("s[\"a\"]", "Expected Integer found `\"a\"`"),
+    ] {
+        let error = engine.eval(source).unwrap_err();
+        assert_eq!(error.message, message, "{}", source);
+    }
+
+    assert_eq!(engine.eval("s[1]").unwrap().as_str(), Some("é"));
+    assert_eq!(engine.eval("s[4]").unwrap().as_str(), Some("o"));
+}
+
+#[test]
+fn logical_operand_errors() {
+    let mut engine = Engine::new();
+
+    for (source, column, length) in [
+        ("true and 1", 10, 1),
+        ("1 or true", 1, 1),
+        ("false or \"x\"", 10, 3),
+        ("true and (false or 2)", 20, 1),
+    ] {
+        let error = engine.eval(source).unwrap_err();
+        assert!(error
+            .message
+            .starts_with("An expression must return a boolean"));
+        assert_eq!(
+            (error.span.column, error.span.length),
+            (column, length),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn arithmetic_errors() {
+    let mut engine = Engine::new();
+
+    for (source, message) in [
+        ("1 + 10 / 0", "Division by zero"),
+        ("5 % 0", "Modulo by zero"),
+        ("1.5 // 0.0", "Division by zero"),
+        ("2 ^ 70 / 0", "Division by zero"),
+        ("0 ^ -1", "Division by zero"),
+        ("2 ^ 5000000000", "The exponent 5000000000 is too large"),
+        ("3 ^ 4000000000", "The exponent 4000000000 is too large"),
+        ("(2 ^ 100) ^ 50000", "The exponent 50000 is too large"),
+        ("\"ab\" * -1", "Cannot repeat a String -1 times"),
+        (
+            "\"ab\" * 9000000000000000000",
+            "The String is too long to repeat 9000000000000000000 times",
+        ),
+        (
+            "random(5, 5)",
+            "Cannot pick a random number between 5 and 5",
+        ),
+        (
+            "random(2.5, 1)",
+            "Cannot pick a random number between 2.5 and 1",
+        ),
+        ("cos(\"x\")", "Cosine works only with numbers"),
+        ("range(1.5, 3)", "Range start and stop must be Integers"),
+    ] {
+        let error = engine.eval(source).unwrap_err();
+        assert_eq!(error.message, message, "{}", source);
+        assert_eq!(error.span.line, 1);
+    }
+
+    let error = engine.eval("def x = 0\n1 + 10 / x").unwrap_err();
+    assert_eq!((error.span.line, error.span.column), (2, 5));
+
+    assert_eq!(engine.eval("2 ^ -1").unwrap().as_float(), Some(0.5));
+    assert_eq!(engine.eval("1 ^ 4000000000").unwrap().as_int(), Some(1));
+    assert_eq!(engine.eval("(-1) ^ 4000000001").unwrap().as_int(), Some(-1));
+}
+
+#[test]
+fn files_and_modules() {
+    let dir = std::env::temp_dir().join(format!("flush-engine-{}", std::process::id()));
+    create_dir_all(&dir).unwrap();
+    write(dir.join("shared.flush"), "def counter = 1\n").unwrap();
+    write(
+        dir.join("left.flush"),
+        format!(
+            "load \"{}\"\ndef left() {{ return counter }}\n",
+            dir.join("shared.flush").display()
+        ),
+    )
+    .unwrap();
+    write(
+        dir.join("right.flush"),
+        format!(
+            "load \"{}\"\ndef right() {{ return counter + 1 }}\n",
+            dir.join("shared.flush").display()
+        ),
+    )
+    .unwrap();
+
+    let mut engine = Engine::new();
+
+    engine.eval_file(dir.join("left.flush")).unwrap();
+    engine.eval_file(dir.join("right.flush")).unwrap();
+
+    assert_eq!(engine.call("left", vec![]).unwrap().as_int(), Some(1));
+    assert_eq!(engine.call("right", vec![]).unwrap().as_int(), Some(2));
+    assert!(engine.eval_file(dir.join("missing.flush")).is_err());
+}
@@ -0,0 +1,109 @@
+use std::fs::{remove_file, write};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SCRIPTS: AtomicUsize = AtomicUsize::new(0);
+
+// Runs `source` as a script and returns what it printed to stderr, without colors
+fn error(source: &str) -> String {
+    let script = std::env::temp_dir().join(format!(
+        "flush-errors-{}-{}.flush",
+        std::process::id(),
+        SCRIPTS.fetch_add(1, Ordering::SeqCst)
+    ));
+    write(&script, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_flush"))
+        .arg(&script)
+        .output()
+        .unwrap();
+    remove_file(&script).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut plain = String::new();
+    let mut chars = stderr.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+
+    plain
+}
+
+#[test]
+fn unary() {
+    for (source, message) in [
+        ("-\"a\"", "Cannot negate \"a\""),
+        ("-[1]", "Cannot negate [1]"),
+        ("-true", "Cannot negate true"),
+        ("not 1", "An expression must return a boolean, not `1`"),
+    ] {
+        let stderr = error(&format!("def main() {{ printLn({}) }}", source));
+        assert!(stderr.contains(message), "{}: {}", source, stderr);
+    }
+}
+
+#[test]
+fn arity() {
+    let functions = "def f(a, b = 2) { return a + b }\ndef g(a, ...rest) { return rest }\n";
+
+    for (call, message) in [
+        (
+            "f(1, 2, 3)",
+            "Too many arguments for `f`, expected `1 to 2` given `3`!",
+        ),
+        (
+            "f()",
+            "Not enough arguments for `f`, expected `1 to 2` given `0`: `a` is missing!",
+        ),
+        ("f(1, c = 2)", "`f` has no parameter named `c`!"),
+        ("f(1, a = 2)", "The argument `a` of `f` is given twice!"),
+        (
+            "g()",
+            "Not enough arguments for `g`, expected `at least 1` given `0`: `a` is missing!",
+        ),
+        ("g(1, 2, x = 3)", "`g` has no parameter named `x`!"),
+    ] {
+        let stderr = error(&format!("{}def main() {{ printLn({}) }}", functions, call));
+        assert!(stderr.contains(message), "{}: {}", call, stderr);
+    }
+}
+
+#[test]
+fn spans() {
+    // The `file:line:column` the error points at
+    let position = |source: &str| {
+        let stderr = error(source);
+        let location = stderr.lines().find_map(|line| line.strip_prefix(" --> "));
+        let location = location.unwrap_or_else(|| panic!("No location in {}", stderr));
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next().unwrap().parse::<usize>().unwrap();
+        let line = parts.next().unwrap().parse::<usize>().unwrap();
+
+        (line, column)
+    };
+
+    assert_eq!(
+        position("def x = 1\ndef y = [1, 2]\ndef main() {\n    printLn(x + y[5])\n}"),
+        (4, 17)
+    );
+    assert_eq!(
+        position(
+            "def f(a) { return a + \"s\" }\ndef g() {\n    return 1 + f(2)\n}\ndef main() { g() }"
+        ),
+        (1, 19)
+    );
+    assert_eq!(
+        position("def main() {\n    def s = \"abc\"\n    s[1] * \"x\" + 2\n}"),
+        (3, 5)
+    );
+    assert_eq!(
+        position("def main() {\n    def x = \"a\"\n    1 + -x\n}"),
+        (3, 9)
+    );
+    assert_eq!(position("def x = 1 +"), (1, 11));
+}
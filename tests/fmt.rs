@@ -0,0 +1,133 @@
+use std::fs::{read_dir, read_to_string};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+fn flush(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_flush"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn format(input: &str) -> Result<String, String> {
+    let output = flush(&["fmt", "-"], input);
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
+
+fn flush_files(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+    let mut files: Vec<PathBuf> = read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("flush"))
+        .collect();
+
+    files.sort();
+
+    files
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    let mut failures = vec![];
+
+    for file in flush_files("examples")
+        .into_iter()
+        .chain(flush_files("tests/scripts"))
+    {
+        let result = format(&read_to_string(&file).unwrap()).and_then(|once| {
+            let twice = format(&once)?;
+            Ok((once, twice))
+        });
+
+        match result {
+            Ok((once, twice)) if once != twice => failures.push(format!(
+                "{}\n--- once ---\n{}--- twice ---\n{}",
+                file.display(),
+                once,
+                twice
+            )),
+            Ok(_) => {}
+            Err(e) => failures.push(format!("{}\n{}", file.display(), e)),
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn formatting_preserves_behaviour() {
+    let mut failures = vec![];
+
+    for script in flush_files("tests/scripts") {
+        let expected = read_to_string(script.with_extension("out")).unwrap();
+        let formatted = match format(&read_to_string(&script).unwrap()) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                failures.push(format!("{}\n{}", script.display(), e));
+                continue;
+            }
+        };
+        let output = flush(&["-"], &formatted);
+        let actual = String::from_utf8_lossy(&output.stdout);
+
+        if actual != expected {
+            failures.push(format!(
+                "{}\n--- expected ---\n{}--- actual ---\n{}",
+                script.display(),
+                expected,
+                actual
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn examples_are_formatted() {
+    let mut args = vec!["fmt".to_string(), "--check".to_string()];
+
+    args.extend(
+        flush_files("examples")
+            .iter()
+            .map(|file| file.to_string_lossy().to_string()),
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_flush"))
+        .args(&args)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn comments_are_kept() {
+    let input = "# header\ndef main() { # brace\n    def a = 1   # trailing\n\n\n    # own line\n    if (a == 1) { printLn(a) } else {\n        # empty else\n    }\n}\n# end\n";
+    let expected = "# header\ndef main() { # brace\n    def a = 1 # trailing\n\n    # own line\n    if (a == 1) {\n        printLn(a)\n    } else {\n        # empty else\n    }\n}\n# end\n";
+
+    assert_eq!(format(input).unwrap(), expected);
+}
@@ -0,0 +1,150 @@
+use flush::{Engine, Value};
+use std::rc::Rc;
+
+fn engines() -> [Engine; 2] {
+    [Engine::new(), Engine::new().with_vm()]
+}
+
+fn int(value: Value) -> i64 {
+    value.as_int().unwrap()
+}
+
+#[test]
+fn cyclic_graphs() {
+    for mut engine in engines() {
+        engine
+            .eval(
+                "def link(n) {
+                    def nodes = []
+
+                    for (i in range(0, n)) {
+                        push(nodes, {\"id\": i, \"next\": 0, \"all\": nodes})
+                    }
+
+                    for (i in range(0, n)) {
+                        nodes[i][\"next\"] = nodes[(i + 1) % n]
+                    }
+                }",
+            )
+            .unwrap();
+        engine.eval("gcCollect()").unwrap();
+
+        let before = int(engine.eval("gcStats()[\"tracked\"]").unwrap());
+
+        engine.eval("link(50)").unwrap();
+
+        assert_eq!(int(engine.eval("gcCollect()").unwrap()), 51);
+        assert_eq!(int(engine.eval("gcStats()[\"tracked\"]").unwrap()), before);
+    }
+}
+
+#[test]
+fn temporaries() {
+    for mut engine in engines() {
+        engine
+            .eval(
+                "def churn() {
+                    gcCollect()
+                    return 0
+                }",
+            )
+            .unwrap();
+
+        let list = engine
+            .eval("[[1, 2], churn(), {\"three\": [3]}, [4] + [churn()]]")
+            .unwrap();
+
+        assert_eq!(list.to_string(), "[[1, 2], 0, {\"three\": [3]}, [4, 0]]");
+
+        engine
+            .eval("def twice = [1] + [] push(twice, twice)")
+            .unwrap();
+        engine.eval("twice = 0").unwrap();
+
+        assert_eq!(int(engine.eval("gcCollect()").unwrap()), 1);
+    }
+}
+
+#[test]
+fn separate_engines() {
+    let mut first = Engine::new();
+    let mut second = Engine::new().with_vm();
+
+    for engine in [&mut first, &mut second] {
+        engine.eval("gcCollect()").unwrap();
+        engine
+            .eval("def cycle() { def list = [] push(list, list) }")
+            .unwrap();
+    }
+
+    first.eval("cycle() cycle()").unwrap();
+    second.eval("cycle()").unwrap();
+
+    assert_eq!(int(second.eval("gcCollect()").unwrap()), 1);
+    assert_eq!(int(first.eval("gcCollect()").unwrap()), 2);
+    assert_eq!(int(second.eval("gcStats()[\"freed\"]").unwrap()), 1);
+}
+
+#[test]
+fn dropped_engine() {
+    for mut engine in engines() {
+        let token = Rc::new(());
+        let held = token.clone();
+
+        engine
+            .register("token", move |_, _| {
+                let _ = &held;
+                Ok(Value::none())
+            })
+            .unwrap();
+        engine
+            .eval("def even(n) { if (n == 0) { return true } return odd(n - 1) }")
+            .unwrap();
+        engine
+            .eval("def odd(n) { if (n == 0) { return false } return even(n - 1) }")
+            .unwrap();
+
+        assert_eq!(Rc::strong_count(&token), 2);
+        drop(engine);
+        assert_eq!(Rc::strong_count(&token), 1);
+    }
+}
+
+#[test]
+fn host_references() {
+    for mut engine in engines() {
+        let list = engine
+            .eval("def cycle = [1] push(cycle, cycle) cycle")
+            .unwrap();
+        engine.eval("cycle = 0").unwrap();
+
+        assert_eq!(int(engine.eval("gcCollect()").unwrap()), 0);
+        assert_eq!(list.to_list().unwrap().len(), 2);
+
+        drop(list);
+
+        assert_eq!(int(engine.eval("gcCollect()").unwrap()), 1);
+
+        let dict = Value::from(vec![Value::from(1)]);
+        engine.set("kept", dict.clone());
+        engine.eval("push(kept, kept) kept = 0").unwrap();
+
+        assert_eq!(int(engine.eval("gcCollect()").unwrap()), 0);
+        assert_eq!(dict.to_string(), "[1, [...]]");
+    }
+}
+
+#[test]
+fn threshold() {
+    for mut engine in engines() {
+        engine
+            .eval("for (i in range(0, 50000)) { def node = {} node[\"self\"] = node }")
+            .unwrap();
+
+        let collections = int(engine.eval("gcStats()[\"collections\"]").unwrap());
+        let tracked = int(engine.eval("gcStats()[\"tracked\"]").unwrap());
+
+        assert!(collections >= 4, "{} collections", collections);
+        assert!(tracked < 20000, "{} objects tracked", tracked);
+    }
+}
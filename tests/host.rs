@@ -0,0 +1,204 @@
+use flush::{check_arity, get_arg, BigInt, Engine, FlushError, Kind, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+fn engines() -> [Engine; 2] {
+    [Engine::new(), Engine::new().with_vm()]
+}
+
+#[test]
+fn host_functions() {
+    for mut engine in engines() {
+        engine
+            .register("add", |_, args| {
+                check_arity(args, 2, 2)?;
+                Ok((get_arg::<i64>(args, 0)? + get_arg::<i64>(args, 1)?).into())
+            })
+            .unwrap();
+        engine
+            .register("sum", |_, args| {
+                Ok(get_arg::<Vec<f64>>(args, 0)?.iter().sum::<f64>().into())
+            })
+            .unwrap();
+        engine
+            .register("apply", |ctx, args| {
+                check_arity(args, 2, 2)?;
+                ctx.call(args[0].clone(), vec![args[1].clone()])
+            })
+            .unwrap();
+
+        assert_eq!(engine.eval("add(1, 2 * 3)").unwrap().as_int(), Some(7));
+        assert_eq!(engine.eval("sum([1, 2.5])").unwrap().as_float(), Some(3.5));
+        assert_eq!(
+            engine
+                .eval("apply(def (x) { return x + 1 }, 41)")
+                .unwrap()
+                .as_int(),
+            Some(42)
+        );
+
+        let error = engine.eval("add(1)").unwrap_err();
+        assert_eq!(error.message, "Expected 2 arguments, given 1");
+        assert_eq!(error.span.column, 1);
+
+        let error = engine.eval("add(1, \"2\")").unwrap_err();
+        assert_eq!(
+            error.message,
+            "Expected Int, found \"2\" for the argument 2"
+        );
+
+        let error = engine.eval("add(1, b = 2)").unwrap_err();
+        assert_eq!(error.message, "`add` does not accept named arguments!");
+
+        let error = engine
+            .eval("apply(def (x) { return x + missing }, 1)")
+            .unwrap_err();
+        assert_eq!(error.message, "The variable `missing` cannot be found!");
+        assert_eq!(error.trace.len(), 1);
+        assert_eq!(engine.eval("add(2, 3)").unwrap().as_int(), Some(5));
+    }
+}
+
+#[test]
+fn host_state() {
+    for mut engine in engines() {
+        let output = Rc::new(RefCell::new(vec![]));
+        let captured = output.clone();
+
+        engine
+            .register("record", move |_, args| {
+                captured.borrow_mut().push(args[0].to_string());
+                Ok(Value::none())
+            })
+            .unwrap();
+        engine
+            .register("bump", |ctx, _| {
+                let count = ctx
+                    .get("count")
+                    .map(i64::try_from)
+                    .transpose()?
+                    .unwrap_or(0);
+                ctx.set("count", (count + 1).into());
+                Ok(Value::none())
+            })
+            .unwrap();
+
+        engine
+            .eval("def main() { record(\"hi\") bump() bump() record(count) }")
+            .unwrap();
+        engine.call("main", vec![]).unwrap();
+
+        assert_eq!(*output.borrow(), vec!["\"hi\"", "2"]);
+    }
+}
+
+#[test]
+fn registration() {
+    for mut engine in engines() {
+        let error = engine
+            .register("printLn", |_, _| Ok(Value::none()))
+            .unwrap_err();
+        assert_eq!(
+            error.message,
+            "Cannot register `printLn`, a builtin function has this name!"
+        );
+
+        engine.eval("def double(n) { return n * 2 }").unwrap();
+
+        let error = engine
+            .register("double", |_, _| Ok(Value::none()))
+            .unwrap_err();
+        assert_eq!(
+            error.message,
+            "Cannot register `double`, a global with this name already exists!"
+        );
+        assert_eq!(engine.eval("double(2)").unwrap().as_int(), Some(4));
+
+        engine.register("answer", |_, _| Ok(42.into())).unwrap();
+        assert_eq!(engine.eval("answer()").unwrap().as_int(), Some(42));
+        assert_eq!(engine.get("answer").unwrap().kind(), Kind::Function);
+
+        engine.eval("def answer() { return 7 }").unwrap();
+        assert_eq!(engine.eval("answer()").unwrap().as_int(), Some(7));
+        assert!(engine.register("answer", |_, _| Ok(Value::none())).is_err());
+    }
+}
+
+#[test]
+fn conversions() {
+    let list = Value::from(vec![1, 2, 3]);
+    assert_eq!(Vec::<i64>::try_from(list).unwrap(), vec![1, 2, 3]);
+
+    let mut dict = HashMap::new();
+    dict.insert("half".to_string(), 0.5);
+    let dict = HashMap::<String, f64>::try_from(Value::from(dict)).unwrap();
+    assert_eq!(dict["half"], 0.5);
+
+    assert_eq!(Option::<String>::try_from(Value::none()).unwrap(), None);
+    assert_eq!(
+        Option::<String>::try_from(Value::from("flush")).unwrap(),
+        Some("flush".to_string())
+    );
+    assert!(Value::from(None::<i64>).is_none());
+    assert_eq!(Value::from(true).as_bool(), Some(true));
+    assert_eq!(Value::from(2).as_float(), None);
+    assert!(f64::try_from(Value::from(2)).unwrap() == 2.0);
+
+    let error: FlushError = String::try_from(Value::from(1)).unwrap_err();
+    assert_eq!(error.message, "Expected String, found 1");
+    assert_eq!(
+        Vec::<i64>::try_from(Value::from(vec![Value::from(1), Value::from("a")]))
+            .unwrap_err()
+            .message,
+        "Expected Int, found \"a\""
+    );
+}
+
+#[test]
+fn big_integers() {
+    let big = BigInt::from(i64::MAX) + 1u32;
+
+    assert_eq!(Value::from(big.clone()).as_int(), None);
+    assert_eq!(Value::from(big.clone()).as_bigint(), Some(big.clone()));
+    assert_eq!(Value::from(BigInt::from(-5)).as_int(), Some(-5));
+    assert_eq!(BigInt::try_from(Value::from(big.clone())).unwrap(), big);
+    assert_eq!(BigInt::try_from(Value::from(3)).unwrap(), BigInt::from(3));
+    assert_eq!(
+        i64::try_from(Value::from(big)).unwrap_err().message,
+        "The Int 9223372036854775808 does not fit in 64 bits"
+    );
+    assert_eq!(
+        i32::try_from(Value::from(1i64 << 40)).unwrap_err().message,
+        "The Int 1099511627776 does not fit in 32 bits"
+    );
+
+    let mut engine = Engine::new();
+    let product = engine.eval("4294967296 * 4294967296").unwrap();
+
+    assert_eq!(product.to_string(), "18446744073709551616");
+    assert_eq!(
+        BigInt::try_from(product).unwrap(),
+        BigInt::from(u64::MAX) + 1u32
+    );
+}
+
+#[test]
+fn arity() {
+    let args = [Value::from(1), Value::from(2)];
+
+    assert!(check_arity(&args, 1, 3).is_ok());
+    assert_eq!(
+        check_arity(&args, 1, 1).unwrap_err().message,
+        "Expected 1 argument, given 2"
+    );
+    assert_eq!(
+        check_arity(&args, 3, usize::MAX).unwrap_err().message,
+        "Expected at least 3 arguments, given 2"
+    );
+    assert_eq!(
+        check_arity(&args, 3, 4).unwrap_err().message,
+        "Expected 3 to 4 arguments, given 2"
+    );
+}
@@ -0,0 +1,244 @@
+use serde_json::{json, Value};
+use std::fs::{create_dir_all, write};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    id: u64,
+}
+
+impl Client {
+    fn start(dir: &PathBuf) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_flush"))
+            .arg("lsp")
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Self {
+            child,
+            stdin,
+            stdout,
+            id: 0,
+        };
+
+        let capabilities = client.request("initialize", json!({}))["capabilities"].clone();
+        assert_eq!(capabilities["definitionProvider"], true);
+        client.notify("initialized", json!({}));
+
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let content = message.to_string();
+
+        write!(
+            self.stdin,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        )
+        .unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+
+            match header.trim_end().split_once(": ") {
+                Some(("Content-Length", value)) => length = value.parse().unwrap(),
+                _ => break,
+            }
+        }
+
+        let mut content = vec![0; length];
+        self.stdout.read_exact(&mut content).unwrap();
+
+        serde_json::from_slice(&content).unwrap()
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.id += 1;
+        self.send(json!({ "jsonrpc": "2.0", "id": self.id, "method": method, "params": params }));
+
+        let response = self.receive();
+        assert_eq!(response["id"], self.id);
+
+        response["result"].clone()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn open(&mut self, uri: &str, text: &str) -> Value {
+        self.notify(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": uri, "languageId": "flush", "version": 1, "text": text } }),
+        );
+
+        let diagnostics = self.receive();
+        assert_eq!(diagnostics["method"], "textDocument/publishDiagnostics");
+
+        diagnostics["params"]["diagnostics"].clone()
+    }
+
+    fn at(&mut self, method: &str, uri: &str, line: u64, character: u64) -> Value {
+        self.request(
+            method,
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character },
+            }),
+        )
+    }
+
+    fn stop(mut self) -> i32 {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+
+        self.child.wait().unwrap().code().unwrap()
+    }
+}
+
+fn workspace(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("flush-lsp-{}-{}", name, std::process::id()));
+    create_dir_all(&dir).unwrap();
+
+    dir.canonicalize().unwrap()
+}
+
+const MAIN: &str = "load \"lib.flush\"
+
+def add(a, b = 2) {
+    return a + b
+}
+
+def main() {
+    printLn(add(double(1)))
+    printLn(missing)
+}
+";
+
+#[test]
+fn language_server() {
+    let dir = workspace("server");
+    write(
+        dir.join("lib.flush"),
+        "def double(x) {\n    return x * 2\n}\n",
+    )
+    .unwrap();
+
+    let uri = format!("file://{}/main.flush", dir.display());
+    let lib_uri = format!("file://{}/lib.flush", dir.display());
+    let mut client = Client::start(&dir);
+
+    let diagnostics = client.open(&uri, MAIN);
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(diagnostics[0]["code"], "undefined");
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({ "start": { "line": 8, "character": 12 }, "end": { "line": 8, "character": 19 } })
+    );
+
+    let definition = client.at("textDocument/definition", &uri, 7, 13);
+    assert_eq!(definition["uri"], uri);
+    assert_eq!(
+        definition["range"]["start"],
+        json!({ "line": 2, "character": 0 })
+    );
+
+    let definition = client.at("textDocument/definition", &uri, 7, 17);
+    assert_eq!(definition["uri"], lib_uri);
+    assert_eq!(
+        definition["range"]["start"],
+        json!({ "line": 0, "character": 0 })
+    );
+
+    let hover = client.at("textDocument/hover", &uri, 7, 13);
+    assert_eq!(
+        hover["contents"]["value"],
+        "```flush\ndef add(a, b = 2)\n```"
+    );
+
+    let hover = client.at("textDocument/hover", &uri, 7, 6);
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("builtin function"));
+
+    let completion = client.at("textDocument/completion", &uri, 8, 12);
+    let labels: Vec<&str> = completion
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    for label in ["add", "double", "main", "printLn", "while"] {
+        assert!(labels.contains(&label), "{}", label);
+    }
+
+    let symbols = client.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": uri } }),
+    );
+    let names: Vec<&str> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| symbol["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["add", "main"]);
+    assert_eq!(symbols[0]["detail"], "def add(a, b = 2)");
+
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{ "text": "def main(){printLn(1)}\n" }],
+        }),
+    );
+    let diagnostics = client.receive();
+    assert_eq!(diagnostics["params"]["diagnostics"], json!([]));
+
+    let edits = client.request(
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": uri }, "options": { "tabSize": 4, "insertSpaces": true } }),
+    );
+    assert_eq!(edits[0]["newText"], "def main() {\n    printLn(1)\n}\n");
+    assert_eq!(
+        edits[0]["range"]["end"],
+        json!({ "line": 1, "character": 0 })
+    );
+
+    client.send(json!({ "jsonrpc": "2.0", "id": 0, "method": "workspace/unknown" }));
+    assert_eq!(client.receive()["error"]["code"], -32601);
+
+    assert_eq!(client.stop(), 0);
+}
+
+#[test]
+fn syntax_errors() {
+    let dir = workspace("syntax");
+    let uri = format!("file://{}/broken.flush", dir.display());
+    let mut client = Client::start(&dir);
+
+    let diagnostics = client.open(&uri, "def main() {\n    printLn(\"unterminated)\n}\n");
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+
+    assert_eq!(client.stop(), 0);
+}
@@ -0,0 +1,36 @@
+use std::fs::{read_dir, read_to_string};
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn scripts() {
+    let scripts_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+    let mut failures = vec![];
+
+    for entry in read_dir(&scripts_dir).unwrap() {
+        let script = entry.unwrap().path();
+
+        if script.extension().and_then(|ext| ext.to_str()) != Some("flush") {
+            continue;
+        }
+
+        let expected = read_to_string(script.with_extension("out")).unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_flush"))
+            .arg(&script)
+            .output()
+            .unwrap();
+        let actual = String::from_utf8_lossy(&output.stdout);
+
+        if actual != expected || !output.status.success() {
+            failures.push(format!(
+                "{}\n--- expected ---\n{}--- actual ---\n{}{}",
+                script.display(),
+                expected,
+                actual,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
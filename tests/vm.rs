@@ -0,0 +1,178 @@
+use std::fs::{create_dir_all, read_dir, remove_dir_all, write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn flush(args: &[&str], script: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_flush"))
+        .args(args)
+        .arg(script)
+        .output()
+        .unwrap()
+}
+
+fn scripts(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+    let mut scripts: Vec<PathBuf> = read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("flush"))
+        .collect();
+
+    scripts.sort();
+    scripts
+}
+
+fn differences(scripts: &[PathBuf]) -> Vec<String> {
+    let mut failures = vec![];
+
+    for script in scripts {
+        let interpreter = flush(&["run"], script);
+        let vm = flush(&["run", "--vm"], script);
+
+        if interpreter.stdout != vm.stdout
+            || interpreter.stderr != vm.stderr
+            || interpreter.status.code() != vm.status.code()
+        {
+            failures.push(format!(
+                "{}\n--- interpreter ({:?}) ---\n{}{}--- vm ({:?}) ---\n{}{}",
+                script.display(),
+                interpreter.status.code(),
+                String::from_utf8_lossy(&interpreter.stdout),
+                String::from_utf8_lossy(&interpreter.stderr),
+                vm.status.code(),
+                String::from_utf8_lossy(&vm.stdout),
+                String::from_utf8_lossy(&vm.stderr),
+            ));
+        }
+    }
+
+    failures
+}
+
+#[test]
+fn examples() {
+    let failures = differences(&scripts("examples"));
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn test_scripts() {
+    let failures = differences(&scripts("tests/scripts"));
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn runtime_errors() {
+    let dir = std::env::temp_dir().join(format!("flush-vm-{}", std::process::id()));
+    create_dir_all(&dir).unwrap();
+
+    let sources = [
+        "def inner(x) { return x + \"s\" }\ndef main() { printLn(\"before\") inner(1) }\n",
+        "def f(a, b) { return a }\ndef main() { f(1) }\n",
+        "def f(n) { return f(n + 1) }\ndef main() { f(0) }\n",
+        "def main() {\n    def x = 1\n    def x = 2\n}\n",
+        "def main() { printLn(undefinedVar) }\n",
+        "def main() { prnt(1) }\n",
+        "def main() { for (main in [1]) {} }\n",
+        "def main() {\n    for (item in [1]) {\n        def item = 2\n    }\n}\n",
+        "def main() { if (1) {} }\n",
+        "def main() { printLn(true and 1) }\n",
+        "def main() { printLn(false or (true and \"x\")) }\n",
+        "def main() { exit(3) }\n",
+        "def main() { def f = def () { break } f() }\n",
+        "def g = 1\ndef main() { printLn(g) def g = 2 printLn(g) }\n",
+        "def main() { def x = 1 def i = 0 while (i < 1) { printLn(x) def x = 2 printLn(x) i = i + 1 } }\n",
+        "def main() {\n    def x = 1\n    def i = 0\n    while (i < 2) {\n        def x = i * 10\n        def f = def () { return x }\n        printLn(f(), x)\n        i = i + 1\n    }\n    for (y in [1]) { def x = y + 4 printLn(x) }\n    printLn(x)\n    def x = 3\n}\n",
+        "def main() { def d = {} d[\"d\"] = d printLn(d[\"x\"]) }\n",
+        "def main() { def y = 1 def i = 0 while (i < 1) { if (false) { def y = 2 } printLn(y) i = i + 1 } }\n",
+        "printLn(1)\n",
+        "def main() { def s = \"héllo\" printLn(s[1], s[4]) printLn(s[5]) }\n",
+    ];
+    let mut scripts = vec![];
+
+    for (i, source) in sources.iter().enumerate() {
+        let script = dir.join(format!("error_{}.flush", i));
+        write(&script, source).unwrap();
+        scripts.push(script);
+    }
+
+    let failures = differences(&scripts);
+    remove_dir_all(&dir).unwrap();
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn top_level_return() {
+    let dir = std::env::temp_dir().join(format!("flush-vm-return-{}", std::process::id()));
+    create_dir_all(&dir).unwrap();
+
+    let library = dir.join("library.flush");
+    write(&library, "def shared = 1\nreturn 0\ndef hidden = 2\n").unwrap();
+
+    let sources = [
+        "return 1\ndef main() { printLn(\"skipped\") }\n".to_string(),
+        "for (i in [1, 2]) { for (j in [3]) { return i + j } }\ndef main() { printLn(\"skipped\") }\n"
+            .to_string(),
+        "def i = 0\nwhile (true) { i = i + 1 if (i == 3) { return i } }\ndef main() { printLn(\"skipped\") }\n"
+            .to_string(),
+        format!(
+            "load \"{}\"\ndef main() {{ printLn(shared) printLn(hidden) }}\n",
+            library.display()
+        ),
+    ];
+    let mut scripts = vec![];
+
+    for (i, source) in sources.iter().enumerate() {
+        let script = dir.join(format!("return_{}.flush", i));
+        write(&script, source).unwrap();
+        scripts.push(script);
+    }
+
+    let failures = differences(&scripts);
+    let outputs: Vec<Output> = scripts
+        .iter()
+        .map(|script| flush(&["run"], script))
+        .collect();
+    remove_dir_all(&dir).unwrap();
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+
+    for output in &outputs[..3] {
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+    }
+
+    assert_eq!(String::from_utf8_lossy(&outputs[3].stdout), "1\n");
+    assert!(String::from_utf8_lossy(&outputs[3].stderr).contains("`hidden` cannot be found"));
+}
+
+#[test]
+fn modules() {
+    let dir = std::env::temp_dir().join(format!("flush-vm-modules-{}", std::process::id()));
+    create_dir_all(&dir).unwrap();
+
+    let library = dir.join("library.flush");
+    write(
+        &library,
+        "def shared = \"library\"\ndef twice(n) { return n * factor }\ndef factor = 2\n",
+    )
+    .unwrap();
+
+    let script = dir.join("main.flush");
+    write(
+        &script,
+        format!(
+            "load \"{}\"\ndef main() {{ printLn(twice(21), shared) }}\n",
+            library.display()
+        ),
+    )
+    .unwrap();
+
+    let failures = differences(&[script]);
+    remove_dir_all(&dir).unwrap();
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}